@@ -2,7 +2,13 @@
 
 set -euo pipefail
 
-for i in $(seq 1 24);
+# Usage: ./run-all.sh [-j N] [--dot DIR] [DAY...]
+# Set FEATURES=alloc-stats to also report the allocations of each part.
+bins="--bin runner"
+for i in $(seq 1 25);
 do
-  cargo run --bin day$i
+  bins="$bins --bin day$i"
 done
+
+cargo build $bins --features "${FEATURES:-}"
+target/debug/runner "$@"
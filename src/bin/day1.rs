@@ -1,51 +1,430 @@
-use std::fs;
+// Usage: day1 [--lang english|swedish|german]... [--word WORD=DIGIT]... [--ignore-case] [--input FILE | --explain FILE]
+// The vocabulary options change part 2. With --input only part 2 of FILE is solved, use - for stdin.
+// With --explain each line of FILE is shown with the tokens found in it.
+// The input is read a line at a time and doesn't have to be UTF-8, so it can be as large as you like.
+use std::{collections::VecDeque, env, fmt, fs::File, io::{self, BufRead, BufReader}};
 
-fn get_lines(filename: &str) -> Vec<String> {
-    fs::read_to_string(filename).unwrap().trim().lines().map(|line| line.to_string()).collect()
+use aoc2023::stats::part;
+
+fn open(filename: &str) -> BufReader<File> {
+    BufReader::new(File::open(filename).unwrap_or_else(|e| panic!("Could not open {filename}: {e}")))
 }
 
-fn solve1(lines: &Vec<String>) -> u32 {
-    lines.iter()
-        .map(|line| {
-            let digits: Vec<u32> = line.chars().filter_map(|c| c.to_digit(10)).collect();
-            digits[0] * 10 + digits.last().unwrap()
-        })
-        .sum()
+fn open_or_stdin(filename: &str) -> Box<dyn BufRead> {
+    if filename == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(open(filename))
+    }
+}
+
+#[derive(Debug)]
+enum CalibrationError {
+    NoDigits { line: usize }, // numbered from 1
+    Io(io::Error),
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CalibrationError::NoDigits { line } => write!(f, "line {line} has no digits"),
+            CalibrationError::Io(e) => write!(f, "could not read input: {e}"),
+        }
+    }
+}
+
+impl From<io::Error> for CalibrationError {
+    fn from(e: io::Error) -> CalibrationError {
+        CalibrationError::Io(e)
+    }
+}
+
+// Calls f with each line and its number, without the line ending, until it returns an error.
+// Blank lines are skipped.
+fn for_each_line<E: From<io::Error>>(
+    mut input: impl BufRead,
+    mut f: impl FnMut(usize, &[u8]) -> Result<(), E>
+) -> Result<(), E> {
+    let mut buf = vec![];
+    let mut number = 0;
+    loop {
+        buf.clear();
+        if input.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+        let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if !line.is_empty() {
+            f(number, line)?;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Language {
+    English,
+    Swedish,
+    German,
+}
+
+impl Language {
+    fn words(self) -> [&'static str; 9] {
+        match self {
+            Language::English => ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"],
+            Language::Swedish => ["ett", "två", "tre", "fyra", "fem", "sex", "sju", "åtta", "nio"],
+            Language::German => ["eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun"],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Language> {
+        match name {
+            "english" => Some(Language::English),
+            "swedish" => Some(Language::Swedish),
+            "german" => Some(Language::German),
+            _ => None,
+        }
+    }
+}
+
+// The words that count as digits
+#[derive(Debug, Clone)]
+struct Vocabulary {
+    words: Vec<(String, u32)>,
+    ignore_case: bool, // only for ascii letters
+}
+
+impl Vocabulary {
+    fn digits() -> Vocabulary {
+        let words = (0..10).map(|d| (d.to_string(), d)).collect();
+        Vocabulary { words, ignore_case: false }
+    }
+
+    // The vocabulary of part 2
+    fn english() -> Vocabulary {
+        Vocabulary::digits().language(Language::English)
+    }
+
+    fn language(self, lang: Language) -> Vocabulary {
+        lang.words().iter().zip(1..).fold(self, |vocab, (word, d)| vocab.word(word, d))
+    }
+
+    fn word(mut self, word: &str, value: u32) -> Vocabulary {
+        self.words.push((word.to_string(), value));
+        self
+    }
+
+    fn ignore_case(mut self) -> Vocabulary {
+        self.ignore_case = true;
+        self
+    }
+
+    fn scanner(&self) -> Scanner {
+        let fold = |b: u8| if self.ignore_case { b.to_ascii_lowercase() } else { b };
+        let patterns: Vec<Vec<u8>> = self.words.iter().map(|(w, _)| w.bytes().map(fold).collect()).collect();
+        let reversed: Vec<Vec<u8>> = patterns.iter().map(|p| p.iter().rev().copied().collect()).collect();
+        Scanner {
+            forward: Automaton::new(&patterns),
+            backward: Automaton::new(&reversed),
+            vocab: self.clone(),
+            max_len: patterns.iter().map(|p| p.len()).max().unwrap_or(0),
+        }
+    }
+}
+
+// Aho-Corasick automaton over bytes, with the transitions of every state filled in
+struct Automaton {
+    next: Vec<[usize; 256]>,
+    matches: Vec<Vec<usize>>, // the patterns ending in each state, including the ones through its fail link
+    lens: Vec<usize>,
+}
+
+impl Automaton {
+    fn new(patterns: &[Vec<u8>]) -> Automaton {
+        const NONE: usize = usize::MAX;
+        let mut next = vec![[NONE; 256]];
+        let mut matches = vec![vec![]];
+        for (id, pattern) in patterns.iter().enumerate() {
+            let mut state = 0;
+            for &b in pattern {
+                if next[state][b as usize] == NONE {
+                    next[state][b as usize] = next.len();
+                    next.push([NONE; 256]);
+                    matches.push(vec![]);
+                }
+                state = next[state][b as usize];
+            }
+            matches[state].push(id);
+        }
+
+        // Breadth first, so the fail links of shorter prefixes are done first
+        let mut fail = vec![0; next.len()];
+        let mut queue = VecDeque::new();
+        for slot in next[0].iter_mut() {
+            match *slot {
+                NONE => *slot = 0,
+                child => queue.push_back(child),
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            let fail_next = next[fail[state]];
+            for (slot, on_fail) in next[state].iter_mut().zip(fail_next) {
+                match *slot {
+                    NONE => *slot = on_fail,
+                    child => {
+                        fail[child] = on_fail;
+                        let inherited = matches[on_fail].clone();
+                        matches[child].extend(inherited);
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+
+        let lens = patterns.iter().map(|p| p.len()).collect();
+        Automaton { next, matches, lens }
+    }
+
+    // All matches as (start, pattern), in the order they end
+    fn find<'a>(&'a self, bytes: impl Iterator<Item = u8> + 'a) -> impl Iterator<Item = (usize, usize)> + 'a {
+        bytes.enumerate()
+            .scan(0, move |state, (i, b)| {
+                *state = self.next[*state][b as usize];
+                Some((i + 1, *state))
+            })
+            .flat_map(move |(end, state)| self.matches[state].iter().map(move |&id| (end - self.lens[id], id)))
+    }
+
+    // The match that starts first, the longest one if several start there, and the last added pattern if they
+    // are the same word, so later words override earlier ones. Matches are found by where they end, so we
+    // keep looking until no match can start before the best one.
+    fn first(&self, bytes: impl Iterator<Item = u8>, max_len: usize) -> Option<(usize, usize)> {
+        let mut best: Option<(usize, usize)> = None;
+        for (start, id) in self.find(bytes) {
+            if let Some((best_start, best_id)) = best {
+                if start + self.lens[id] > best_start + max_len {
+                    break;
+                }
+                if start > best_start || start == best_start && (self.lens[id], id) <= (self.lens[best_id], best_id) {
+                    continue;
+                }
+            }
+            best = Some((start, id));
+        }
+        best
+    }
 }
 
-fn solve2(lines: &Vec<String>) -> u32 {
-    lines.iter()
-        .map(|line| {
-            let digits: Vec<u32> = (0..line.len())
-                .filter_map(|i| starting_digit(&line[i..]))
-                .collect();
-            digits[0] * 10 + digits.last().unwrap()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Token {
+    start: usize,
+    word: usize, // index in the vocabulary
+}
+
+struct Scanner {
+    forward: Automaton,
+    backward: Automaton,
+    vocab: Vocabulary,
+    max_len: usize,
+}
+
+impl Scanner {
+    fn fold(&self, b: u8) -> u8 {
+        if self.vocab.ignore_case { b.to_ascii_lowercase() } else { b }
+    }
+
+    fn first(&self, line: &[u8]) -> Option<Token> {
+        let bytes = line.iter().map(|&b| self.fold(b));
+        self.forward.first(bytes, self.max_len).map(|(start, word)| Token { start, word })
+    }
+
+    // The same search on the reversed line, with the reversed words
+    fn last(&self, line: &[u8]) -> Option<Token> {
+        let bytes = line.iter().rev().map(|&b| self.fold(b));
+        self.backward.first(bytes, self.max_len).map(|(rev_start, word)| {
+            let start = line.len() - rev_start - self.backward.lens[word];
+            Token { start, word }
         })
-        .sum()
+    }
+
+    fn tokens(&self, line: &[u8]) -> Vec<Token> {
+        let mut tokens: Vec<Token> = self.forward.find(line.iter().map(|&b| self.fold(b)))
+            .map(|(start, word)| Token { start, word })
+            .collect();
+        tokens.sort_by_key(|t| t.start);
+        tokens
+    }
+
+    fn value(&self, token: Token) -> u32 {
+        self.vocab.words[token.word].1
+    }
+
+    fn calibration(&self, line: &[u8]) -> Option<u32> {
+        Some(self.value(self.first(line)?) * 10 + self.value(self.last(line)?))
+    }
+
+    fn describe(&self, token: Token) -> String {
+        format!("{}@{}", self.vocab.words[token.word].0, token.start)
+    }
+
+    fn explain(&self, line: &[u8]) -> String {
+        let text = String::from_utf8_lossy(line);
+        let tokens = self.tokens(line).into_iter().map(|t| self.describe(t)).collect::<Vec<_>>().join(" ");
+        match (self.first(line), self.last(line)) {
+            (Some(first), Some(last)) => format!(
+                "{text}\n  tokens: {tokens}\n  first {}, last {}: {}",
+                self.describe(first), self.describe(last), self.calibration(line).unwrap()
+            ),
+            _ => format!("{text}\n  no digits"),
+        }
+    }
+}
+
+// Stops at the first line without digits
+fn solve(input: impl BufRead, vocab: &Vocabulary) -> Result<u64, CalibrationError> {
+    let scanner = vocab.scanner();
+    let mut sum = 0;
+    for_each_line(input, |number, line| -> Result<(), CalibrationError> {
+        let value = scanner.calibration(line).ok_or(CalibrationError::NoDigits { line: number })?;
+        sum += value as u64;
+        Ok(())
+    })?;
+    Ok(sum)
 }
 
-fn starting_digit(input: &str) -> Option<u32> {
-    let numbers = vec!["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+fn solve1(input: impl BufRead) -> Result<u64, CalibrationError> {
+    solve(input, &Vocabulary::digits())
+}
+
+fn solve2(input: impl BufRead, vocab: &Vocabulary) -> Result<u64, CalibrationError> {
+    solve(input, vocab)
+}
 
-    let digit = input.chars().next().and_then(|c| c.to_digit(10));
-    if digit.is_some() {
-        digit
+// WORD=DIGIT
+fn parse_word(s: &str) -> Option<(String, u32)> {
+    let (word, d) = s.split_once('=')?;
+    let d = d.parse().ok().filter(|&d| d <= 9)?;
+    Some((word.to_string(), d))
+}
+
+struct Options {
+    vocab: Vocabulary,
+    input: Option<String>,
+    explain: Option<String>,
+}
+
+fn parse_args() -> Options {
+    let mut langs = vec![];
+    let mut words = vec![];
+    let mut ignore_case = false;
+    let mut input = None;
+    let mut explain = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{arg} needs a value"));
+        match arg.as_str() {
+            "--lang" => langs.push(Language::from_name(&value()).expect("Unknown language")),
+            "--word" => words.push(parse_word(&value()).expect("Words are given as WORD=DIGIT with a digit 0-9")),
+            "--ignore-case" => ignore_case = true,
+            "--input" => input = Some(value()),
+            "--explain" => explain = Some(value()),
+            _ => panic!("Unknown argument {arg}"),
+        }
+    }
+    let mut vocab = if langs.is_empty() {
+        Vocabulary::english()
     } else {
-        (0..numbers.len()).find_map(|i| {
-            input
-                .starts_with(numbers[i])
-                .then_some((i+1) as u32)
-        })
+        langs.into_iter().fold(Vocabulary::digits(), |vocab, lang| vocab.language(lang))
+    };
+    vocab = words.iter().fold(vocab, |vocab, (word, d)| vocab.word(word, *d));
+    if ignore_case {
+        vocab = vocab.ignore_case();
     }
+    Options { vocab, input, explain }
 }
 
 fn main() {
-    let example1 = get_lines("inputs/day1_ex1.txt");
-    let example2 = get_lines("inputs/day1_ex2.txt");
-    let input = get_lines("inputs/day1.txt");
-
-    println!("example1: {}", solve1(&example1));
-    println!("solution1: {}", solve1(&input));
-    println!("example2: {}", solve2(&example2));
-    println!("solution2: {}", solve2(&input));
+    let opts = parse_args();
+    if let Some(file) = opts.input {
+        match solve2(open_or_stdin(&file), &opts.vocab) {
+            Ok(sum) => println!("{file}: {sum}"),
+            Err(e) => {
+                eprintln!("{file}: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(file) = opts.explain {
+        let scanner = opts.vocab.scanner();
+        for_each_line(open_or_stdin(&file), |_, line| {
+            println!("{}", scanner.explain(line));
+            io::Result::Ok(())
+        }).unwrap();
+        return;
+    }
+
+    let show = |res: Result<u64, CalibrationError>| res.unwrap_or_else(|e| panic!("{e}"));
+    println!("example1: {}", show(solve1(open("inputs/day1_ex1.txt"))));
+    println!("solution1: {}", show(part("sol1", || solve1(open("inputs/day1.txt")))));
+    println!("example2: {}", show(solve2(open("inputs/day1_ex2.txt"), &opts.vocab)));
+    println!("solution2: {}", show(part("sol2", || solve2(open("inputs/day1.txt"), &opts.vocab))));
+}
+
+#[test]
+fn test_scanner() {
+    let english = Vocabulary::english().scanner();
+    assert_eq!(Some(82), english.calibration(b"eightwo"));
+    assert_eq!(Some(18), english.calibration(b"xoneightx"), "overlapping words share the 'e'");
+    assert_eq!(Some(76), english.calibration(b"7pqrstsixteen"));
+    assert_eq!(None, english.calibration(b"abc"));
+    assert_eq!(vec!["eight@0", "two@4", "three@7"], english.tokens(b"eightwothree").into_iter()
+        .map(|t| english.describe(t)).collect::<Vec<_>>());
+
+    // the first match to end isn't the first one to start
+    let nested = Vocabulary::digits().word("abcd", 1).word("bc", 2).scanner();
+    assert_eq!(Some(12), nested.calibration(b"abcdbc"));
+    assert_eq!(Some(11), nested.calibration(b"abcd"));
+
+    let swedish = Vocabulary::digits().language(Language::Swedish).scanner();
+    assert_eq!(Some(28), swedish.calibration("xtvåttax".as_bytes()));
+    assert_eq!(None, swedish.calibration(b"eightwo"));
+
+    // a word given again counts as the later digit, also when it only matches ignoring case
+    let redefined = Vocabulary::english().word("one", 7).scanner();
+    assert_eq!(Some(77), redefined.calibration(b"xonex"));
+    assert_eq!(Some(72), redefined.calibration(b"onetwo"));
+    let redefined = Vocabulary::english().word("ONE", 7).ignore_case().scanner();
+    assert_eq!(Some(77), redefined.calibration(b"xOnex"));
+
+        let caseless = Vocabulary::digits().language(Language::German).ignore_case().scanner();
+    assert_eq!(Some(35), caseless.calibration("DREIfünF".as_bytes()));
+    assert_eq!(None, Vocabulary::digits().language(Language::German).scanner().calibration(b"DREI"));
+}
+
+#[test]
+fn test_parse_word() {
+    assert_eq!(Some(("one".to_string(), 7)), parse_word("one=7"));
+    assert_eq!(Some(("noll".to_string(), 0)), parse_word("noll=0"));
+    assert_eq!(None, parse_word("one=12"));
+    assert_eq!(None, parse_word("one=-1"));
+    assert_eq!(None, parse_word("one"));
+}
+
+#[test]
+fn test_stream() {
+    use std::io::Cursor;
+
+    assert_eq!(12 + 99 + 44, solve1(Cursor::new(b"a1b2\r\n\xff9\xfe\n\n4")).unwrap());
+    let err = solve1(Cursor::new(b"1\nabc\n2\n")).unwrap_err();
+    assert!(matches!(err, CalibrationError::NoDigits { line: 2 }));
+    assert_eq!("line 2 has no digits", err.to_string());
+}
+
+aoc2023::answers! {
+    ex1("inputs/day1_ex1.txt"): |f| solve1(open(f)).unwrap() => 142;
+    sol1("inputs/day1.txt"): |f| solve1(open(f)).unwrap() => 54644;
+    ex2("inputs/day1_ex2.txt"): |f| solve2(open(f), &Vocabulary::english()).unwrap() => 281;
+    sol2("inputs/day1.txt"): |f| solve2(open(f), &Vocabulary::english()).unwrap() => 53348;
 }
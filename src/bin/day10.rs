@@ -1,21 +1,17 @@
-use std::{ops::{Add, Sub}, fs, collections::HashSet};
+use std::{fs, collections::HashSet};
+
+use aoc2023::{coord::{Coord, Dir}, grid::{flood_fill, upscale, Connectivity}, stats::part};
 
 fn main() {
     let ex1 = parse("inputs/day10_ex.txt");
     let ex2 = parse("inputs/day10_ex2.txt");
     let ex3 = parse("inputs/day10_ex3.txt");
     let inp = parse("inputs/day10.txt");
-    assert!(8 == solve1(&ex1));
-    assert!(8 == solve2(&ex2));
-    assert!(10 == solve2(&ex3));
-
-    let sol1 = solve1(&inp);
-    assert!(sol1 == 6717);
-    println!("sol1: {sol1}");
-
-    let sol2 = solve2(&inp);
-    assert!(sol2 == 381);
-    println!("sol2: {sol2}");
+    println!("ex1: {}", solve1(&ex1));
+    println!("sol1: {}", part("sol1", || solve1(&inp)));
+    println!("ex2: {}", solve2(&ex2));
+    println!("ex3: {}", solve2(&ex3));
+    println!("sol2: {}", part("sol2", || solve2(&inp)));
 }
 
 fn parse(filename: &str) -> Board {
@@ -26,66 +22,16 @@ fn parse(filename: &str) -> Board {
     Board { chars }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coord {
-    x: i32,
-    y: i32,
-}
-
-impl Coord {
-    fn new(x: i32, y: i32) -> Self {
-        Coord {x, y}
-    }
-
-    fn up() -> Coord {
-        Coord::new(0, -1)
-    }
-
-    fn right() -> Coord {
-        Coord::new(1, 0)
-    }
-
-    fn down() -> Coord {
-        Coord::new(0, 1)
-    }
-
-    fn left() -> Coord {
-        Coord::new(-1, 0)
-    }
-
-    fn dirs() -> Vec<Coord> {
-        vec![Self::up(), Self::left(), Self::down(), Self::right()]
-    }
-}
-
-impl Add for Coord {
-    type Output = Self;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Coord::new(self.x + rhs.x, self.y + rhs.y)
-    }
-}
-
-impl Sub for Coord {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Coord::new(self.x - rhs.x, self.y - rhs.y)
-    }
-}
-
 struct Board {
     chars: Vec<Vec<char>>
 }
 
 impl Board {
     fn get(&self, c: Coord) -> char {
-        if c.x < 0 || c.y < 0 || c.x as usize >= self.chars[0].len() || c.y as usize >= self.chars.len() {
+        if c.x < 0 || c.y < 0 || c.x_u() >= self.chars[0].len() || c.y_u() >= self.chars.len() {
             return '.';
         }
-        let y: usize = c.y.try_into().unwrap();
-        let x: usize = c.x.try_into().unwrap();
-        self.chars[y][x]
+        self.chars[c.y_u()][c.x_u()]
     }
 }
 
@@ -94,46 +40,40 @@ fn solve1(board: &Board) -> usize {
     find_path(board).len() / 2
 }
 
+// Blow up every tile to 3x3 so that the gaps between pipes become passable, then flood fill the outside.
+// The tiles' corners are always empty, so the outside is connected around the edge of the map.
 fn solve2(board: &Board) -> usize {
     let path = find_path(board);
-
-    let mut change = vec!['|', 'J', 'L'];
-    if (path[1] - path[0]).y == -1 {
-        change.push('S');
-    }
-
-    let path: HashSet<&Coord> = HashSet::from_iter(path.iter());
-
-    let mut cnt = 0;
-    let mut inside = false;
-    for (row, y) in board.chars.iter().zip(0..) {
-        for (c, x) in row.iter().zip(0..) {
-            let coord = Coord::new(x, y);
-            if path.contains(&coord) {
-                print!("{c}");
-                if change.contains(&c) {
-                    inside = !inside;
-                }
-            } else if inside {
-                cnt += 1;
-                print!("X");
-            } else {
-                print!(" ");
+    let start = path[0];
+    let start_conns = vec![path[1] - start, path[path.len() - 1] - start];
+    let path: HashSet<Coord> = HashSet::from_iter(path);
+
+    let big = upscale(&board.chars, 3, |pos, &c| {
+        let mut block = vec![vec!['.'; 3]; 3];
+        if path.contains(&pos) {
+            block[1][1] = '#';
+            let conns = if pos == start { start_conns.clone() } else { neigh(c) };
+            for conn in conns {
+                block[(1 + conn.y) as usize][(1 + conn.x) as usize] = '#';
             }
         }
-        println!("");
-    }
-    cnt
+        block
+    });
+    let outside = flood_fill(&big, &[Coord::new(0, 0)], Connectivity::Four, |&c| c == '.');
+
+    (0..board.chars.len()).flat_map(|y| (0..board.chars[0].len()).map(move |x| Coord::new_u(x, y)))
+        .filter(|pos| !path.contains(pos) && !outside.contains(&(*pos * 3 + Coord::new(1, 1))))
+        .count()
 }
 
 
 fn find_path(board: &Board) -> Vec<Coord> {
     let y = board.chars.iter().position(|row| row.contains(&'S')).unwrap();
     let x = board.chars[y].iter().position(|c| *c == 'S').unwrap();
-    let start = Coord::new(x.try_into().unwrap(), y.try_into().unwrap());
+    let start = Coord::new_u(x, y);
 
-    Coord::dirs().iter().find_map(|dir| {
-        let path = follow(&board, start, start + *dir);
+    Dir::all().find_map(|dir| {
+        let path = follow(board, start, start.go(dir));
         (path.len() > 0).then_some(path)
     }).unwrap()
 }
@@ -166,4 +106,12 @@ fn neigh(c: char) -> Vec<Coord> {
         '.' => vec![],
         _ => panic!("unexpected"),
     }
-}
\ No newline at end of file
+}
+
+aoc2023::answers! {
+    ex1("inputs/day10_ex.txt"): |f| solve1(&parse(f)) => 8;
+    sol1("inputs/day10.txt"): |f| solve1(&parse(f)) => 6717;
+    ex2("inputs/day10_ex2.txt"): |f| solve2(&parse(f)) => 8;
+    ex3("inputs/day10_ex3.txt"): |f| solve2(&parse(f)) => 10;
+    sol2("inputs/day10.txt"): |f| solve2(&parse(f)) => 381;
+}
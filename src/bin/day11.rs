@@ -1,5 +1,7 @@
 use std::fs;
 
+use aoc2023::stats::part;
+
 fn parse(file: &str) -> Vec<Vec<char>> {
     fs::read_to_string(file).unwrap().trim().lines().map(|line| {
         line.chars().collect()
@@ -43,14 +45,14 @@ fn main() {
     let ex1 = parse("inputs/day11_ex1.txt");
     let inp = parse("inputs/day11.txt");
 
-    let ex1_res = solve(&ex1, 2);
-    assert!(ex1_res == 374);
-
-    let sol1 = solve(&inp, 2);
-    println!("sol1: {sol1}");
-    assert!(sol1 == 10228230);
+    println!("ex1: {}", solve(&ex1, 2));
+    println!("sol1: {}", part("sol1", || solve(&inp, 2)));
+    println!("sol2: {}", part("sol2", || solve(&inp, 1000000)));
+}
 
-    let sol2 = solve(&inp, 1000000);
-    println!("sol2: {sol2}");
-    assert!(sol2 == 447073334102);
-}
\ No newline at end of file
+aoc2023::answers! {
+    ex1("inputs/day11_ex1.txt"): |f| solve(&parse(f), 2) => 374;
+    sol1("inputs/day11.txt"): |f| solve(&parse(f), 2) => 10228230;
+    ex2("inputs/day11_ex1.txt"): |f| solve(&parse(f), 100) => 8410;
+    sol2("inputs/day11.txt"): |f| solve(&parse(f), 1000000) => 447073334102;
+}
@@ -1,5 +1,7 @@
 use std::fs;
 
+use aoc2023::stats::part;
+
 // Use dynamic programming to optimize
 fn combos(broken: &[usize], pattern: &str) -> usize {
     // solution array for each prefix of pattern and broken
@@ -92,8 +94,15 @@ fn parse(file: &str) -> Vec<(String, Vec<usize>)> {
 fn main() {
     let ex1 = parse("inputs/day12_ex1.txt");
     let inp = parse("inputs/day12.txt");
-    assert!(solve1(&ex1) == 21);
-    assert!(solve2(&ex1) == 525152);
-    println!("sol1: {}", solve1(&inp));
-    println!("sol2: {}", solve2(&inp));
-}
\ No newline at end of file
+    println!("ex1: {}", solve1(&ex1));
+    println!("ex2: {}", solve2(&ex1));
+    println!("sol1: {}", part("sol1", || solve1(&inp)));
+    println!("sol2: {}", part("sol2", || solve2(&inp)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day12_ex1.txt"): |f| solve1(&parse(f)) => 21;
+    sol1("inputs/day12.txt"): |f| solve1(&parse(f)) => 6488;
+    ex2("inputs/day12_ex1.txt"): |f| solve2(&parse(f)) => 525152;
+    sol2("inputs/day12.txt"): |f| solve2(&parse(f)) => 815364548481;
+}
@@ -1,5 +1,7 @@
 use std::{fs, cmp::min};
 
+use aoc2023::stats::part;
+
 fn parse(file: &str) -> Vec<Vec<Vec<char>>> {
     fs::read_to_string(file).unwrap().trim().split("\n\n").map(|part| {
         part.lines().map(|line| line.chars().collect()).collect()
@@ -41,8 +43,15 @@ fn solve(inp: &Vec<Vec<Vec<char>>>, smears: usize) -> usize {
 fn main() {
     let ex = parse("inputs/day13_ex1.txt");
     let inp = parse("inputs/day13.txt");
-    assert!(solve(&ex, 0) == 405);
-    assert!(solve(&ex, 1) == 400);
-    println!("sol1: {}", solve(&inp, 0));
-    println!("sol2: {}", solve(&inp, 1));
-}
\ No newline at end of file
+    println!("ex1: {}", solve(&ex, 0));
+    println!("ex2: {}", solve(&ex, 1));
+    println!("sol1: {}", part("sol1", || solve(&inp, 0)));
+    println!("sol2: {}", part("sol2", || solve(&inp, 1)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day13_ex1.txt"): |f| solve(&parse(f), 0) => 405;
+    sol1("inputs/day13.txt"): |f| solve(&parse(f), 0) => 27300;
+    ex2("inputs/day13_ex1.txt"): |f| solve(&parse(f), 1) => 400;
+    sol2("inputs/day13.txt"): |f| solve(&parse(f), 1) => 29276;
+}
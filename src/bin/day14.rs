@@ -1,5 +1,7 @@
 use std::fs;
 
+use aoc2023::stats::part;
+
 fn parse(file: &str) -> Vec<Vec<char>> {
     fs::read_to_string(file).unwrap().trim().lines().map(|line| {
         line.chars().collect()
@@ -94,8 +96,15 @@ fn score(board: &Vec<Vec<char>>) -> usize {
 fn main() {
     let ex = parse("inputs/day14_ex.txt");
     let inp = parse("inputs/day14.txt");
-    assert!(solve1(&ex) == 136);
-    assert!(solve2(&ex, 1000000000) == 64);
-    println!("sol1: {}", solve1(&inp));
-    println!("sol2: {}", solve2(&inp, 1000000000));
-}
\ No newline at end of file
+    println!("ex1: {}", solve1(&ex));
+    println!("ex2: {}", solve2(&ex, 1000000000));
+    println!("sol1: {}", part("sol1", || solve1(&inp)));
+    println!("sol2: {}", part("sol2", || solve2(&inp, 1000000000)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day14_ex.txt"): |f| solve1(&parse(f)) => 136;
+    sol1("inputs/day14.txt"): |f| solve1(&parse(f)) => 109833;
+    ex2("inputs/day14_ex.txt"): |f| solve2(&parse(f), 1000000000) => 64;
+    sol2("inputs/day14.txt"): |f| solve2(&parse(f), 1000000000) => 99875;
+}
@@ -1,3 +1,5 @@
+use aoc2023::stats::part;
+
 fn parse(file: &str) -> Vec<String> {
     std::fs::read_to_string(file).unwrap().trim().split(',').map(|s| s.to_string()).collect()
 }
@@ -50,7 +52,14 @@ fn put<'a>(boxes: &mut Boxes<'a>, lbl: &'a str, val: usize) {
 
 fn main() {
     let inp = parse("inputs/day15.txt");
-    println!("sol1: {}", solve1(&inp));
-    println!("sol2: {}", solve2(&inp));
+    println!("sol1: {}", part("sol1", || solve1(&inp)));
+    println!("sol2: {}", part("sol2", || solve2(&inp)));
+
+}
 
-}
\ No newline at end of file
+aoc2023::answers! {
+    ex1("inputs/day15_ex.txt"): |f| solve1(&parse(f)) => 1320;
+    sol1("inputs/day15.txt"): |f| solve1(&parse(f)) => 504449;
+    ex2("inputs/day15_ex.txt"): |f| solve2(&parse(f)) => 145;
+    sol2("inputs/day15.txt"): |f| solve2(&parse(f)) => 262044;
+}
@@ -1,4 +1,6 @@
-use std::{collections::HashSet, cmp::max};
+use std::collections::HashSet;
+
+use aoc2023::{coord::{Coord, CoordMap, Dir}, parallel::par_map, stats::part};
 
 fn parse(file: &str) -> Vec<Vec<char>> {
     std::fs::read_to_string(file).unwrap().trim().lines().map(|line| {
@@ -6,85 +8,76 @@ fn parse(file: &str) -> Vec<Vec<char>> {
     }).collect()
 }
 
-fn solve1(map: &Vec<Vec<char>>, start: (i32, i32, i32)) -> usize {
-    let mut stack: Vec<(i32, i32, i32)> = vec![start];
+fn solve1(map: &Vec<Vec<char>>, start: (Coord, Dir)) -> usize {
+    let mut stack: Vec<(Coord, Dir)> = vec![start];
     let mut seen = HashSet::new();
     while !stack.is_empty() {
-        let (x, y, d) = stack.pop().unwrap();
-        if x < 0 || y < 0 || x >= map[0].len() as i32 || y >= map.len() as i32 {
+        let (pos, d) = stack.pop().unwrap();
+        if !map.contains(pos) {
             continue;
         }
-        if seen.contains(&(x, y, d)) {
+        if seen.contains(&(pos, d)) {
             continue;
         }
-        seen.insert((x, y, d));
-        match map[y as usize][x as usize] {
-            '.' => stack.push(step(x, y, d)),
+        seen.insert((pos, d));
+        match *map.at(pos) {
+            '.' => stack.push(step(pos, d)),
             '\\' => {
                 let d2 = match d {
-                    0 => 1,
-                    1 => 0,
-                    2 => 3,
-                    3 => 2,
-                    _ => panic!("unexpected"),
+                    Dir::Right => Dir::Down,
+                    Dir::Down => Dir::Right,
+                    Dir::Left => Dir::Up,
+                    Dir::Up => Dir::Left,
                 };
-                stack.push(step(x, y, d2));
+                stack.push(step(pos, d2));
             },
             '/' => {
                 let d2 = match d {
-                    0 => 3,
-                    1 => 2,
-                    2 => 1,
-                    3 => 0,
-                    _ => panic!("unexpected"),
+                    Dir::Right => Dir::Up,
+                    Dir::Down => Dir::Left,
+                    Dir::Left => Dir::Down,
+                    Dir::Up => Dir::Right,
                 };
-                stack.push(step(x, y, d2));
+                stack.push(step(pos, d2));
             }
-            '|' if d == 1 || d == 3 => stack.push(step(x, y, d)),
-            '|' => {
-                stack.push(step(x, y, (d + 1) % 4));
-                stack.push(step(x, y, (d + 3) % 4));
-            },
-            '-' if d == 0 || d == 2 => stack.push(step(x, y, d)),
-            '-' => {
-                stack.push(step(x, y, (d + 1) % 4));
-                stack.push(step(x, y, (d + 3) % 4));
+            '|' if d.is_vertical() => stack.push(step(pos, d)),
+            '-' if d.is_horizontal() => stack.push(step(pos, d)),
+            '|' | '-' => {
+                stack.push(step(pos, d.turn_left()));
+                stack.push(step(pos, d.turn_right()));
             },
             _ => panic!("unexpected"),
         }
     }
-    seen.iter().map(|&(x, y, _)| (x, y)).collect::<HashSet<(i32, i32)>>().len()
+    seen.iter().map(|&(pos, _)| pos).collect::<HashSet<Coord>>().len()
 }
 
 fn solve2(map: &Vec<Vec<char>>) -> usize {
-    let row_max = (0..map.len()).map(|y| {
-        let r = solve1(map, (0, y as i32, 0));
-        let l = solve1(map, (map[0].len() as i32 -1, y as i32, 2));
-        max(r, l)
-    }).max().unwrap();
-    let col_max = (0..map[0].len()).map(|x| {
-        let r = solve1(map, (x as i32, 0, 1));
-        let l = solve1(map, (x as i32, map.len() as i32 -1, 3));
-        max(r, l)
-    }).max().unwrap();
-    max(row_max, col_max)
+    let width = map[0].len();
+    let height = map.len();
+    let starts: Vec<(Coord, Dir)> = (0..height)
+        .flat_map(|y| [(Coord::new_u(0, y), Dir::Right), (Coord::new_u(width - 1, y), Dir::Left)])
+        .chain((0..width).flat_map(|x| [(Coord::new_u(x, 0), Dir::Down), (Coord::new_u(x, height - 1), Dir::Up)]))
+        .collect();
+    par_map(&starts, |&start| solve1(map, start)).into_iter().max().unwrap()
 }
 
-fn step(x: i32,y: i32, d: i32) -> (i32, i32, i32) {
-    match d {
-        0 => (x + 1, y, d),
-        1 => (x, y + 1, d),
-        2 => (x - 1, y, d),
-        3 => (x, y - 1, d),
-        _ => panic!("unexpected")
-    }
+fn step(pos: Coord, d: Dir) -> (Coord, Dir) {
+    (pos.go(d), d)
 }
 
 fn main() {
     let ex = parse("inputs/day16_ex1.txt");
     let inp = parse("inputs/day16.txt");
-    assert!(solve1(&ex, (0, 0, 0)) == 46);
-    assert!(solve2(&ex) == 51);
-    println!("sol1: {}", solve1(&inp, (0, 0, 0)));
-    println!("sol2: {}", solve2(&inp));
-}
\ No newline at end of file
+    println!("ex1: {}", solve1(&ex, (Coord::new(0, 0), Dir::Right)));
+    println!("ex2: {}", solve2(&ex));
+    println!("sol1: {}", part("sol1", || solve1(&inp, (Coord::new(0, 0), Dir::Right))));
+    println!("sol2: {}", part("sol2", || solve2(&inp)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day16_ex1.txt"): |f| solve1(&parse(f), (Coord::new(0, 0), Dir::Right)) => 46;
+    sol1("inputs/day16.txt"): |f| solve1(&parse(f), (Coord::new(0, 0), Dir::Right)) => 7111;
+    ex2("inputs/day16_ex1.txt"): |f| solve2(&parse(f)) => 51;
+    sol2("inputs/day16.txt"): |f| solve2(&parse(f)) => 7831;
+}
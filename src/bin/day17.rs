@@ -1,6 +1,6 @@
 use priority_queue::DoublePriorityQueue;
 
-use aoc2023::coord::{Coord, CoordMap, Dir};
+use aoc2023::{coord::{Coord, CoordMap, Dir}, stats::part};
 
 fn parse(file: &str) -> Vec<Vec<u8>> {
     std::fs::read_to_string(file).unwrap().trim().lines().map(|line| {
@@ -63,8 +63,15 @@ fn neighbours(map: &Vec<Vec<u8>>, min_steps: usize, max_steps: usize, pos: Coord
 fn main() {
     let ex = parse("inputs/day17_ex.txt");
     let inp = parse("inputs/day17.txt");
-    assert!(astar(&ex, 0, 3) == 102);
-    assert!(astar(&ex, 4, 10) == 94);
-    println!("sol1: {}", astar(&inp, 0, 3));
-    println!("sol2: {}", astar(&inp, 4, 10));
-}
\ No newline at end of file
+    println!("ex1: {}", astar(&ex, 0, 3));
+    println!("ex2: {}", astar(&ex, 4, 10));
+    println!("sol1: {}", part("sol1", || astar(&inp, 0, 3)));
+    println!("sol2: {}", part("sol2", || astar(&inp, 4, 10)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day17_ex.txt"): |f| astar(&parse(f), 0, 3) => 102;
+    sol1("inputs/day17.txt"): |f| astar(&parse(f), 0, 3) => 1044;
+    ex2("inputs/day17_ex.txt"): |f| astar(&parse(f), 4, 10) => 94;
+    sol2("inputs/day17.txt"): |f| astar(&parse(f), 4, 10) => 1227;
+}
@@ -1,15 +1,9 @@
-use aoc2023::coord::{Coord, Dir};
+use aoc2023::{coord::{Coord, Dir, Notation}, grid::{flood_fill, Connectivity, SparseGrid}, stats::part};
 
 fn parse(file: &str) -> Vec<(Dir, i64)> {
     std::fs::read_to_string(file).unwrap().trim().lines().map(|line| {
         let parts: Vec<&str> = line.split(" ").collect();
-        let dir = match parts[0] {
-            "U" => Dir::Up,
-            "L" => Dir::Left,
-            "R" => Dir::Right,
-            "D" => Dir::Down,
-            _ => panic!("unexpected dir"),
-        };
+        let dir = parts[0].parse::<Dir>().unwrap();
         (dir, parts[1].parse::<i64>().unwrap())
     }).collect()
 }
@@ -18,13 +12,7 @@ fn parse2(file: &str) -> Vec<(Dir, i64)> {
     std::fs::read_to_string(file).unwrap().trim().lines().map(|line| {
         let parts: Vec<&str> = line.split(" ").collect();
         let hex = i64::from_str_radix(&parts[2][2..7], 16).unwrap();
-        let dir = match parts[2].chars().nth(7).unwrap() {
-            '0' => Dir::Right,
-            '1' => Dir::Down,
-            '2' => Dir::Left,
-            '3' => Dir::Up,
-            c => panic!("unexpected dir {c:?}"),
-        };
+        let dir = Dir::from_char(parts[2].chars().nth(7).unwrap(), Notation::Numeric).unwrap();
         (dir, hex)
     }).collect()
 }
@@ -58,13 +46,48 @@ fn solve(inp: &Vec<(Dir, i64)>) -> i64 {
     area
 }
 
+// Draw the trench, only feasible for part 1
+fn dig(inp: &Vec<(Dir, i64)>) -> SparseGrid<bool> {
+    let mut grid = SparseGrid::new(false);
+    let mut pos = Coord::new(0, 0);
+    for &(dir, n) in inp {
+        for _ in 0..n {
+            pos = pos.go(dir);
+            grid.set(pos, true);
+        }
+    }
+    grid
+}
+
+// Count the cells of the trench's bounding box that can't be reached from its edge
+fn solve_brute(inp: &Vec<(Dir, i64)>) -> usize {
+    let grid = dig(inp);
+    let (min, max) = grid.bounds().unwrap();
+    let edge: Vec<Coord> = (min.x..=max.x).flat_map(|x| [Coord::new(x, min.y), Coord::new(x, max.y)])
+        .chain((min.y..=max.y).flat_map(|y| [Coord::new(min.x, y), Coord::new(max.x, y)]))
+        .collect();
+    let outside = flood_fill(&grid, &edge, Connectivity::Four, |&trench| !trench);
+    grid.width() * grid.height() - outside.len()
+}
+
 fn main() {
     let ex = parse("inputs/day18_ex.txt");
     let inp = parse("inputs/day18.txt");
     let ex2 = parse2("inputs/day18_ex.txt");
     let inp2 = parse2("inputs/day18.txt");
-    assert!(solve(&ex) == 62);
-    assert!(solve(&ex2) == 952408144115);
-    println!("sol1: {}", solve(&inp));
-    println!("sol2: {}", solve(&inp2));
-}
\ No newline at end of file
+    println!("ex1: {}", solve(&ex));
+    println!("ex1 brute: {}", solve_brute(&ex));
+    println!("ex2: {}", solve(&ex2));
+    println!("sol1: {}", part("sol1", || solve(&inp)));
+    println!("sol2: {}", part("sol2", || solve(&inp2)));
+}
+
+aoc2023::answers! {
+    ex_dig("inputs/day18_ex.txt"): |f| dig(&parse(f)).len() => 38;
+    ex1("inputs/day18_ex.txt"): |f| solve(&parse(f)) => 62;
+    ex1_brute("inputs/day18_ex.txt"): |f| solve_brute(&parse(f)) => 62;
+    sol1("inputs/day18.txt"): |f| solve(&parse(f)) => 48652;
+    sol1_brute("inputs/day18.txt"): |f| solve_brute(&parse(f)) => 48652;
+    ex2("inputs/day18_ex.txt"): |f| solve(&parse2(f)) => 952408144115;
+    sol2("inputs/day18.txt"): |f| solve(&parse2(f)) => 45757884535661;
+}
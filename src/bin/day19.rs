@@ -1,5 +1,7 @@
 use std::{collections::HashMap, cmp::{min, max}};
 
+use aoc2023::{dot::{self, Dot}, stats::part};
+
 enum Rule {
     Reject,
     Accept,
@@ -108,11 +110,45 @@ fn combos(start: [usize;4], end: [usize;4], map: &HashMap<String, Vec<Rule>>, lb
     }
 }
 
+// Each workflow is a node with an edge per rule, labelled with the rule's position and condition
+fn to_dot(map: &HashMap<String, Vec<Rule>>) -> Dot {
+    let mut dot = Dot::digraph("day19");
+    dot.node("in").shape("box");
+    dot.node("A").shape("doublecircle");
+    dot.node("R").shape("octagon");
+
+    let mut names: Vec<&String> = map.keys().filter(|&name| name != "A" && name != "R").collect();
+    names.sort();
+    for name in names {
+        for (i, rule) in map[name].iter().enumerate() {
+            let (cond, dest) = match rule {
+                Rule::Accept => (String::new(), "A"),
+                Rule::Reject => (String::new(), "R"),
+                Rule::Goto(dest) => (String::new(), dest.as_str()),
+                Rule::Cmp(attr, op, v, dest) => {
+                    let attr = ['x', 'm', 'a', 's'][*attr];
+                    (format!(" {attr}{op}{v}"), dest.as_str())
+                }
+            };
+            dot.edge(name, dest).label(format!("{}{cond}", i + 1));
+        }
+    }
+    dot
+}
+
 fn main() {
     let ex = parse("inputs/day19_ex.txt");
     let inp = parse("inputs/day19.txt");
-    assert!(solve1(&ex) == 19114);
-    assert!(solve2(&ex.0) == 167409079868000);
-    println!("sol1: {}", solve1(&inp));
-    println!("sol2: {}", solve2(&inp.0));
-}
\ No newline at end of file
+    dot::export("day19", || to_dot(&inp.0));
+    println!("ex1: {}", solve1(&ex));
+    println!("ex2: {}", solve2(&ex.0));
+    println!("sol1: {}", part("sol1", || solve1(&inp)));
+    println!("sol2: {}", part("sol2", || solve2(&inp.0)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day19_ex.txt"): |f| solve1(&parse(f)) => 19114;
+    sol1("inputs/day19.txt"): |f| solve1(&parse(f)) => 432427;
+    ex2("inputs/day19_ex.txt"): |f| solve2(&parse(f).0) => 167409079868000;
+    sol2("inputs/day19.txt"): |f| solve2(&parse(f).0) => 143760172569135;
+}
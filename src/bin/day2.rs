@@ -2,11 +2,13 @@
 // Trying out the nom parser library
 //
 use std::str::FromStr;
-use std::{fs, str::Lines};
+use std::{collections::{BTreeMap, BTreeSet}, fs, ops::RangeInclusive};
 
+use aoc2023::stats::part;
+use itertools::Itertools;
 use nom::{
     IResult,
-    combinator::map_res,
+    combinator::{map, map_res},
     bytes::complete::tag,
     character::complete::{
         digit1,
@@ -25,25 +27,41 @@ fn parse_num<T: FromStr>(input: &str) -> IResult<&str, T> {
     map_res(digit1, |n: &str| n.parse::<T>())(input)
 }
 
-fn parse_color(input: &str) -> IResult<&str, usize> {
-    map_res(alpha1, |s| {
-        match s {
-        "red" => Ok(0),
-        "green" => Ok(1),
-        "blue" => Ok(2),
-        _ => Err(())
-    }})(input)
+// Cubes by colour, either a handful shown from the bag or what the bag holds
+type Cubes = BTreeMap<String, u32>;
+
+#[derive(Debug)]
+struct Game {
+    id: usize,
+    draws: Vec<Cubes>,
+}
+
+// The colours are the ones that appear anywhere in the input
+#[derive(Debug)]
+struct Games {
+    games: Vec<Game>,
+    colours: BTreeSet<String>,
+}
+
+fn parse_color(input: &str) -> IResult<&str, &str> {
+    alpha1(input)
 }
 
-fn parse_pair(input: &str) -> IResult<&str, (i32, usize)> {
+fn parse_pair(input: &str) -> IResult<&str, (u32, &str)> {
     separated_pair(parse_num, tag(" "), parse_color)(input)
 }
 
-fn parse_game(input: &str) -> IResult<&str, Vec<(i32, usize)>> {
-    separated_list1(tag(", "), parse_pair)(input)
+fn parse_draw(input: &str) -> IResult<&str, Cubes> {
+    map(separated_list1(tag(", "), parse_pair), |pairs| {
+        let mut draw = Cubes::new();
+        for (n, colour) in pairs {
+            *draw.entry(colour.to_string()).or_insert(0) += n;
+        }
+        draw
+    })(input)
 }
 
-fn parse_games(input: &str) -> IResult<&str, (usize, Vec<Vec<(i32, usize)>>)> {
+fn parse_game(input: &str) -> IResult<&str, Game> {
     let parse_n = terminated(
         preceded(
             tag("Game "),
@@ -51,47 +69,278 @@ fn parse_games(input: &str) -> IResult<&str, (usize, Vec<Vec<(i32, usize)>>)> {
         ),
         tag(": ")
     );
-    let parse_games = separated_list1(tag("; "), parse_game);
-    tuple((parse_n, parse_games))(input)
+    let parse_draws = separated_list1(tag("; "), parse_draw);
+    map(tuple((parse_n, parse_draws)), |(id, draws)| Game { id, draws })(input)
 }
 
-fn solve1(lines: Lines, limits: Vec<i32>) -> usize {
-    lines.filter_map(|line| {
-        let (rest, (n, games)) = parse_games(line).unwrap();
+fn parse(input: &str) -> Games {
+    let games: Vec<Game> = input.lines().map(|line| {
+        let (rest, game) = parse_game(line).unwrap();
         assert!(rest.is_empty());
+        game
+    }).collect();
+    let colours = games.iter().flat_map(|g| g.draws.iter().flat_map(|d| d.keys().cloned())).collect();
+    Games { games, colours }
+}
+
+fn cubes(counts: &[(&str, u32)]) -> Cubes {
+    counts.iter().map(|&(colour, n)| (colour.to_string(), n)).collect()
+}
+
+impl Game {
+    // The fewest cubes of each colour the bag could have held, for the colours shown in this game
+    fn min_bag(&self) -> Cubes {
+        let mut bag = Cubes::new();
+        for (colour, &n) in self.draws.iter().flatten() {
+            let max = bag.entry(colour.clone()).or_insert(0);
+            *max = (*max).max(n);
+        }
+        bag
+    }
+
+    // Colours missing from the limits can't be in the bag at all
+    fn is_possible(&self, limits: &Cubes) -> bool {
+        self.min_bag().iter().all(|(colour, &n)| n <= limits.get(colour).copied().unwrap_or(0))
+    }
+}
+
+impl Games {
+    fn possible(&self, limits: &Cubes) -> Vec<usize> {
+        self.games.iter().filter(|g| g.is_possible(limits)).map(|g| g.id).collect()
+    }
+
+    fn min_bags(&self) -> Vec<(usize, Cubes)> {
+        self.games.iter().map(|g| (g.id, g.min_bag())).collect()
+    }
+
+    // The product of the minimum bag over all the colours, so a colour a game never shows makes it 0
+    fn power(&self, bag: &Cubes) -> u32 {
+        self.colours.iter().map(|colour| bag.get(colour).copied().unwrap_or(0)).product()
+    }
+
+    // The smallest limit that, used for every colour, makes at least n games possible
+    fn smallest_uniform_limit(&self, n: usize) -> Option<u32> {
+        if n == 0 {
+            return Some(0);
+        }
+        let mut needed: Vec<u32> = self.games.iter()
+            .map(|g| g.min_bag().into_values().max().unwrap_or(0))
+            .collect();
+        needed.sort();
+        needed.get(n - 1).copied()
+    }
+}
+
+// A prior over the total number of cubes in the bag. Given the total, every way of splitting it
+// between the colours is taken to be equally likely.
+struct Prior {
+    weights: Vec<(u32, f64)>,
+}
+
+impl Prior {
+    fn uniform(totals: RangeInclusive<u32>) -> Prior {
+        Prior { weights: totals.map(|t| (t, 1.0)).collect() }
+    }
+
+    fn poisson(mean: f64, max: u32) -> Prior {
+        let mut p = (-mean).exp();
+        let weights = (0..=max).map(|t| {
+            if t > 0 {
+                p *= mean / t as f64;
+            }
+            (t, p)
+        }).collect();
+        Prior { weights }
+    }
+}
+
+fn choose(n: u32, k: u32) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (0..k.min(n - k)).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+// Every bag with the given total that holds at least the minimum bag. Colours the game never shows
+// can still be in the bag.
+fn compositions(min_bag: &Cubes, colours: &[&String], total: u32) -> Vec<Cubes> {
+    fn go(min_bag: &Cubes, colours: &[&String], left: u32, bag: &mut Cubes, out: &mut Vec<Cubes>) {
+        let Some((&colour, rest)) = colours.split_first() else {
+            if left == 0 {
+                out.push(bag.clone());
+            }
+            return;
+        };
+        let min = min_bag.get(colour).copied().unwrap_or(0);
+        let counts = if rest.is_empty() { left..=left } else { min..=left };
+        for n in counts.filter(|&n| n >= min) {
+            bag.insert(colour.clone(), n);
+            go(min_bag, rest, left - n, bag, out);
+        }
+        bag.remove(colour);
+    }
+    let mut out = vec![];
+    go(min_bag, colours, total, &mut Cubes::new(), &mut out);
+    out
+}
+
+#[derive(Debug)]
+struct Estimate {
+    mle: Option<(Cubes, f64)>,
+    posterior: Vec<(Cubes, f64)>, // most probable first
+}
 
-        let is_possible = games.iter().all(|colors| {
-            colors.iter().all(|(n, c)| *n <= limits[*c])
-        });
+impl Estimate {
+    fn total_posterior(&self) -> BTreeMap<u32, f64> {
+        let mut totals = BTreeMap::new();
+        for (bag, p) in self.posterior.iter() {
+            *totals.entry(bag.values().sum()).or_insert(0.0) += p;
+        }
+        totals
+    }
+
+    fn expected(&self) -> BTreeMap<String, f64> {
+        let mut expected = BTreeMap::new();
+        for (bag, p) in self.posterior.iter() {
+            for (colour, &n) in bag {
+                *expected.entry(colour.clone()).or_insert(0.0) += p * n as f64;
+            }
+        }
+        expected
+    }
+}
+
+impl Game {
+    // The chance of the draws if the bag held `bag`. Each reveal takes its cubes without
+    // replacement, and they are put back before the next reveal.
+    fn likelihood(&self, bag: &Cubes) -> f64 {
+        let total = bag.values().sum();
+        self.draws.iter().map(|draw| {
+            let shown = draw.values().sum();
+            let ways: f64 = draw.iter()
+                .map(|(colour, &k)| choose(bag.get(colour).copied().unwrap_or(0), k))
+                .product();
+            ways / choose(total, shown)
+        }).product()
+    }
+
+    fn estimate(&self, colours: &BTreeSet<String>, prior: &Prior) -> Estimate {
+        let min_bag = self.min_bag();
+        let colours = colours.iter().collect_vec();
+        let mut mle: Option<(Cubes, f64)> = None;
+        let mut posterior = vec![];
+        for &(total, weight) in prior.weights.iter() {
+            let bags = compositions(&min_bag, &colours, total);
+            // all splits of the total, feasible or not, share the prior weight
+            let per_bag = weight / choose(total + colours.len() as u32 - 1, colours.len() as u32 - 1);
+            for bag in bags {
+                let l = self.likelihood(&bag);
+                if !matches!(&mle, Some((_, best)) if *best >= l) {
+                    mle = Some((bag.clone(), l));
+                }
+                posterior.push((bag, per_bag * l));
+            }
+        }
+        let evidence: f64 = posterior.iter().map(|(_, p)| p).sum();
+        if evidence > 0.0 {
+            posterior.iter_mut().for_each(|(_, p)| *p /= evidence);
+        }
+        posterior.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Estimate { mle, posterior }
+    }
+}
 
-        is_possible.then_some(n)
-    }).sum()
+fn fmt_cubes(bag: &Cubes) -> String {
+    bag.iter().map(|(colour, n)| format!("{n} {colour}")).join(", ")
 }
 
-fn solve2(lines: Lines) -> i32 {
-    lines.map(|line| {
-        let (_, (_, games)) = parse_games(line).unwrap();
-        power(games)
-    }).sum()
+fn solve1(games: &Games, limits: &Cubes) -> usize {
+    games.possible(limits).iter().sum()
 }
 
-fn power(games: Vec<Vec<(i32, usize)>>) -> i32 {
-    (0..3).map(|color| {
-        let max_num_for_color = games.iter()
-            .flat_map(|colors| {
-                colors.iter().filter_map(|(n, c)| (*c == color).then_some(n))
-            })
-            .max().unwrap();
-        max_num_for_color
-    }).product()
+fn solve2(games: &Games) -> u32 {
+    games.min_bags().iter().map(|(_, bag)| games.power(bag)).sum()
 }
 
 fn main() {
-    let example1 = fs::read_to_string("inputs/day2_ex1.txt").unwrap();
-    let input = fs::read_to_string("inputs/day2.txt").unwrap();
+    let example1 = parse(&fs::read_to_string("inputs/day2_ex1.txt").unwrap());
+    let input = parse(&fs::read_to_string("inputs/day2.txt").unwrap());
+    let limits = cubes(&[("red", 12), ("green", 13), ("blue", 14)]);
+
+    println!("example1: {}", solve1(&example1, &limits));
+    println!("problem1: {}", part("sol1", || solve1(&input, &limits)));
+    println!("example2: {}", solve2(&example1));
+    println!("problem2: {}", part("sol2", || solve2(&input)));
+    println!("uniform limit for all games: {}", input.smallest_uniform_limit(input.games.len()).unwrap());
+
+    let game = &example1.games[0];
+    let estimate = game.estimate(&example1.colours, &Prior::uniform(0..=30));
+    let (mle, _) = estimate.mle.as_ref().unwrap();
+    let (map, p) = &estimate.posterior[0];
+    println!("example game {} mle: {}", game.id, fmt_cubes(mle));
+    println!("example game {} most probable: {} ({:.3})", game.id, fmt_cubes(map), p);
+
+    let estimate = game.estimate(&example1.colours, &Prior::poisson(20.0, 40));
+    let (total, p) = estimate.total_posterior().into_iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+    let expected = estimate.expected().iter().map(|(colour, n)| format!("{n:.1} {colour}")).join(", ");
+    println!("example game {} with ~20 cubes: most likely {} in total ({:.3}), expected {}", game.id, total, p, expected);
+}
+
+#[test]
+fn test_queries() {
+    let games = parse(&fs::read_to_string("inputs/day2_ex1.txt").unwrap());
+    assert_eq!(vec!["blue", "green", "red"], games.colours.iter().collect::<Vec<_>>());
+    assert_eq!(vec![1, 2, 5], games.possible(&cubes(&[("red", 12), ("green", 13), ("blue", 14)])));
+    assert_eq!(vec![2], games.possible(&cubes(&[("red", 4), ("green", 3), ("blue", 4)])));
+    assert_eq!(Vec::<usize>::new(), games.possible(&cubes(&[("red", 20), ("green", 13)])));
+    assert_eq!(cubes(&[("red", 4), ("green", 2), ("blue", 6)]), games.min_bags()[0].1);
+
+    // the games need 6, 4, 20, 15 and 6 of their most common colour
+    assert_eq!(Some(0), games.smallest_uniform_limit(0));
+    assert_eq!(Some(6), games.smallest_uniform_limit(3));
+    assert_eq!(Some(20), games.smallest_uniform_limit(5));
+    assert_eq!(None, games.smallest_uniform_limit(6));
+
+    // any colours work
+    let games = parse("Game 7: 2 cyan, 1 magenta; 3 yellow\nGame 8: 1 cyan, 1 cyan");
+    assert_eq!(vec![8], games.possible(&cubes(&[("cyan", 2)])));
+    assert_eq!(0, games.power(&games.games[1].min_bag()));
+    assert_eq!(6, games.power(&games.games[0].min_bag()));
+}
+
+#[test]
+fn test_estimate() {
+    let games = parse("Game 1: 2 red; 1 red, 1 blue");
+    let game = &games.games[0];
+    assert_eq!(0.25, game.likelihood(&cubes(&[("red", 3), ("blue", 1)])));
+    assert_eq!(0.0, game.likelihood(&cubes(&[("red", 1), ("blue", 1)])));
+
+    // bags of 3 must be 2 red and 1 blue
+    let estimate = game.estimate(&games.colours, &Prior::uniform(3..=3));
+    assert_eq!(1, estimate.posterior.len());
+    assert_eq!(cubes(&[("red", 2), ("blue", 1)]), estimate.mle.unwrap().0);
+
+    let estimate = game.estimate(&games.colours, &Prior::uniform(0..=10));
+    let total: f64 = estimate.posterior.iter().map(|(_, p)| p).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+    assert!(estimate.total_posterior().keys().all(|&t| t >= 3));
+    assert!(estimate.expected()["red"] > estimate.expected()["blue"]);
+
+    // 3 red and 1 blue explain the draws best, 1/4 against 2/9 for the smallest bag
+    let estimate = game.estimate(&games.colours, &Prior::poisson(4.0, 20));
+    assert_eq!(cubes(&[("red", 3), ("blue", 1)]), estimate.mle.unwrap().0);
+    assert!(estimate.posterior[0].1 > estimate.posterior[1].1);
+
+    // a colour never shown can still be in the bag
+    let games = parse("Game 1: 1 red\nGame 2: 1 green");
+    let estimate = games.games[0].estimate(&games.colours, &Prior::uniform(2..=2));
+    assert_eq!(vec![cubes(&[("red", 2), ("green", 0)]), cubes(&[("red", 1), ("green", 1)])],
+               estimate.posterior.iter().map(|(bag, _)| bag.clone()).collect_vec());
+}
 
-    println!("example1: {}", solve1(example1.lines(), vec![12, 13, 14]));
-    println!("problem1: {}", solve1(input.lines(), vec![12, 13, 14]));
-    println!("example2: {}", solve2(example1.lines()));
-    println!("problem2: {}", solve2(input.lines()));
+aoc2023::answers! {
+    ex1("inputs/day2_ex1.txt"): |f| solve1(&parse(&fs::read_to_string(f).unwrap()), &cubes(&[("red", 12), ("green", 13), ("blue", 14)])) => 8;
+    sol1("inputs/day2.txt"): |f| solve1(&parse(&fs::read_to_string(f).unwrap()), &cubes(&[("red", 12), ("green", 13), ("blue", 14)])) => 2632;
+    ex2("inputs/day2_ex1.txt"): |f| solve2(&parse(&fs::read_to_string(f).unwrap())) => 2286;
+    sol2("inputs/day2.txt"): |f| solve2(&parse(&fs::read_to_string(f).unwrap())) => 69629;
 }
@@ -1,5 +1,7 @@
 use std::collections::{VecDeque, HashSet};
 
+use aoc2023::{dot::{self, Dot}, graph::Interner, stats::part};
+
 #[derive(Debug, Clone)]
 struct Entry {
     typ: char,
@@ -7,71 +9,82 @@ struct Entry {
     sources: Vec<usize>,
 }
 
-fn parse(path: &str) -> Vec<Entry> {
+// The entries are indexed by the interned module names
+struct Modules {
+    names: Interner,
+    entries: Vec<Entry>,
+    broadcaster: usize,
+}
+
+fn parse(path: &str) -> Modules {
     let data = std::fs::read_to_string(path).unwrap();
-    let mut lines: Vec<&str> = data.trim().lines().collect();
-    lines.sort();
-    let names: Vec<&str> = lines.iter().map(|line| &line.split(" -> ").next().unwrap()[1..]).collect();
-    let mut map = vec![];
-
-    let get_pos = |name| {
-        names.iter().position(|&n| n == name).unwrap_or_else(|| {
-            names.len()
-        })
-    };
-    let mut sources = vec![vec![]; names.len() + 1];
-    lines.iter().enumerate().for_each(|(cur, line)| {
-        let parts: Vec<&str> = line.split(" -> ").collect();
-        let typ = parts[0].chars().nth(0).unwrap();
-        let dests: Vec<usize> = parts[1].split(", ").map(|s| get_pos(s)).collect();
-        for &dest in dests.iter() {
-            sources[dest].push(cur)
+    let mut names = Interner::new();
+    let lines: Vec<(char, Vec<&str>)> = data.trim().lines().map(|line| {
+        let (lhs, rhs) = line.split_once(" -> ").unwrap();
+        names.intern(lhs.trim_start_matches(['%', '&']));
+        (lhs.chars().next().unwrap(), rhs.split(", ").collect())
+    }).collect();
+
+    let mut entries: Vec<Entry> = lines.into_iter().map(|(typ, dests)| {
+        let dests = dests.into_iter().map(|dest| names.intern(dest)).collect();
+        Entry { typ, dests, sources: vec![] }
+    }).collect();
+    // modules without a line of their own, like rx, are outputs
+    entries.resize(names.len(), Entry { typ: 'o', dests: vec![], sources: vec![] });
+    for cur in 0..entries.len() {
+        for dest in entries[cur].dests.clone() {
+            entries[dest].sources.push(cur);
         }
-        map.push(Entry { typ, dests, sources: vec![] });
-    });
-    map.push(Entry { typ: 'o', dests: vec![], sources: vec![] }); // output
-    map.iter_mut().enumerate().for_each(|(cur, e)| {
-        e.sources = sources[cur].clone();
-    });
-    map
+    }
+
+    let broadcaster = names.id("broadcaster").unwrap();
+    Modules { names, entries, broadcaster }
 }
 
-fn solve1(inp: &Vec<Entry>) -> usize {
-    let (mut ff_states, mut con_states) = init_states(inp);
+fn solve1(m: &Modules) -> usize {
+    let (mut ff_states, mut con_states) = init_states(&m.entries);
 
     let mut highs = 0;
     let mut lows = 0;
     for _ in 0..1000 {
-        let (h, l, _) = push_button(inp, &mut ff_states, &mut con_states, None);
+        let (h, l, _) = push_button(m, &mut ff_states, &mut con_states, None);
         highs += h;
         lows += l;
     }
     highs * lows
 }
 
-fn solve2(inp: &Vec<Entry>) -> usize {
+// The conjunction that feeds rx
+fn rx_guard(m: &Modules) -> usize {
+    let rx = &m.entries[m.names.id("rx").unwrap()];
+    assert!(rx.sources.len() == 1);
+    rx.sources[0]
+}
+
+// Returns the modules feeding the rx guard, with the number of presses until they send a high pulse.
+fn solve2(m: &Modules) -> (usize, Vec<(usize, usize)>) {
     // This uses the fact that there are distinct parts of the graph that cycle independently of each other
-    let subs = subsets(inp);
+    let subs = subsets(m);
     let mut prod = 1;
-    for subset in subs {
-        let (mut ff_states, mut con_states) = init_states(inp);
+    let mut feeders = vec![];
+    for (feeder, subset) in subs {
+        let (mut ff_states, mut con_states) = init_states(&m.entries);
         for i in 1.. {
-            let (_, _, guard_high) = push_button(inp, &mut ff_states, &mut con_states, Some(&subset));
+            let (_, _, guard_high) = push_button(m, &mut ff_states, &mut con_states, Some(&subset));
             if guard_high {
                 prod *= i;
+                feeders.push((feeder, i));
                 break;
             }
         }
     }
-    prod
+    (prod, feeders)
 }
 
-fn subsets(inp: &Vec<Entry>) -> Vec<HashSet<usize>> {
+fn subsets(m: &Modules) -> Vec<(usize, HashSet<usize>)> {
+    let inp = &m.entries;
     let mut res = vec![];
-    let last = &inp[inp.len() - 1];
-    assert!(last.sources.len() == 1);
-    let lastguard = last.sources[0];
-    for end in inp[lastguard].sources.iter() {
+    for end in inp[rx_guard(m)].sources.iter() {
         let mut used = HashSet::new();
         let mut buf = vec![end];
         while !buf.is_empty() {
@@ -82,7 +95,7 @@ fn subsets(inp: &Vec<Entry>) -> Vec<HashSet<usize>> {
         }
         println!("subgroup: {} / {}", used.len(), inp.len());
         assert!(used.len() < inp.len());
-        res.push(used)
+        res.push((*end, used))
     }
     res
 }
@@ -102,11 +115,12 @@ fn init_states(
 
 // This function is a bit ugly since we mix the solution logic for part 1 and part 2
 fn push_button(
-    inp: &Vec<Entry>,
+    m: &Modules,
     ff_states: &mut Vec<bool>,
     con_states: &mut Vec<Vec<bool>>,
     subset: Option<&HashSet<usize>>
 ) -> (usize, usize, bool) {
+    let inp = &m.entries;
     let mut pulses = VecDeque::new();
     let mut guard_high = false;
     let mut highs = 0;
@@ -115,9 +129,9 @@ fn push_button(
     let last_guard = if subset.is_none() {
         10000 // a random id that doesn't exist
     } else {
-        inp[inp.len()-1].sources[0]
+        rx_guard(m)
     };
-    let start = inp.len() - 2;
+    let start = m.broadcaster;
 
     inp[start].dests.iter().for_each(|dest| {
         let do_add = subset.is_none() || subset.is_some_and(|subs| subs.contains(dest));
@@ -159,14 +173,41 @@ fn push_button(
     (lows, highs, guard_high)
 }
 
+// Flip-flops are boxes and conjunctions diamonds, both labelled with their type like in the input
+fn to_dot(m: &Modules) -> Dot {
+    let mut dot = Dot::digraph("day20");
+    for (entry, name) in m.entries.iter().zip(m.names.names()) {
+        match entry.typ {
+            '%' => dot.node(name).label(format!("%{name}")).shape("box"),
+            '&' => dot.node(name).label(format!("&{name}")).shape("diamond"),
+            _ => dot.node(name).shape("doublecircle"),
+        };
+        for &dest in entry.dests.iter() {
+            dot.edge(name, m.names.name(dest));
+        }
+    }
+    dot
+}
 
 fn main() {
     let ex12 = parse("inputs/day20_ex1.txt");
     let ex22 = parse("inputs/day20_ex2.txt");
     let inp2 = parse("inputs/day20.txt");
+    dot::export("day20", || to_dot(&inp2));
 
     println!("ex1: {}", solve1(&ex12));
     println!("ex2: {}", solve1(&ex22));
-    println!("sol2: {}", solve1(&inp2));
-    println!("sol2: {}", solve2(&inp2));
-}
\ No newline at end of file
+    println!("sol2: {}", part("sol1", || solve1(&inp2)));
+    let (sol2, feeders) = part("sol2", || solve2(&inp2));
+    for (feeder, cycle) in feeders {
+        println!("feeder {}: high after {} presses", inp2.names.name(feeder), cycle);
+    }
+    println!("sol2: {}", sol2);
+}
+
+aoc2023::answers! {
+    ex1("inputs/day20_ex1.txt"): |f| solve1(&parse(f)) => 32000000;
+    ex2("inputs/day20_ex2.txt"): |f| solve1(&parse(f)) => 11687500;
+    sol1("inputs/day20.txt"): |f| solve1(&parse(f)) => 839775244;
+    sol2("inputs/day20.txt"): |f| solve2(&parse(f)).0 => 207787533680413;
+}
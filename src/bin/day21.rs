@@ -1,6 +1,4 @@
-use std::collections::HashSet;
-
-use aoc2023::coord::{Coord, Dir, CoordMap};
+use aoc2023::{coord::Coord, grid::{bfs, distance_map, TiledGrid}, stats::part};
 
 fn parse(path: &str) -> (Vec<Vec<char>>, Coord) {
     let map: Vec<Vec<char>> = std::fs::read_to_string(path).unwrap().trim().lines().map(|line| {
@@ -15,13 +13,6 @@ fn parse(path: &str) -> (Vec<Vec<char>>, Coord) {
 }
 
 
-fn neigh(map: &Vec<Vec<char>>, pos: Coord) -> Vec<Coord> {
-    (0..4).filter_map(|i| {
-        let n = pos + Coord::dir(Dir::of_id(i));
-        (map.contains(n) && *map.at(n) != '#').then_some(n)
-    }).collect()
-}
-
 fn solve2b((map, start): &(Vec<Vec<char>>, Coord), steps: usize) -> usize {
     // assume:
     // - width == height
@@ -112,29 +103,23 @@ fn straight_reach_blocks(w: usize, steps: usize) -> usize {
     (steps + mid) / w
 }
 
+// The number of plots reachable in exactly i steps, for every i until the block is covered and the
+// counts just alternate
 fn calculate_plots_for_steps(map: &Vec<Vec<char>>, entry_point: Coord) -> Vec<usize> {
-    let mut coverage = [HashSet::new(), HashSet::new()];
-    coverage[0].insert(entry_point);
-    let mut buf: Vec<Coord> = vec![entry_point];
-
-    let mut plots_for_steps = vec![1];
-    for i in 1.. {
-        let mut new: Vec<Coord> = vec![];
-        while !buf.is_empty() {
-            let next = buf.pop().unwrap();
-            for step in neigh(map, next) {
-                if coverage[i%2].insert(step) {
-                    new.push(step);
-                }
-            }
-        }
-        plots_for_steps.push(coverage[i%2].len());
-        if new.len() == 0 {
-            return plots_for_steps;
-        }
-        buf = new;
+    let dists = distance_map(map, &[entry_point], |&c| c != '#');
+    let dists: Vec<usize> = dists.into_iter().flatten().flatten().collect();
+    let max_dist = *dists.iter().max().unwrap();
+
+    let mut at_dist = vec![0; max_dist + 2];
+    dists.iter().for_each(|&d| at_dist[d] += 1);
+
+    // A plot can be reached in i steps if it's at a smaller distance with the same parity
+    let mut plots_for_steps: Vec<usize> = vec![];
+    for i in 0..at_dist.len() {
+        let prev = if i >= 2 { plots_for_steps[i - 2] } else { 0 };
+        plots_for_steps.push(prev + at_dist[i]);
     }
-    panic!("unreachable")
+    plots_for_steps
 }
 
 fn get_plots_for_steps(plots_for_steps: &Vec<usize>, steps: usize) -> usize {
@@ -146,8 +131,33 @@ fn get_plots_for_steps(plots_for_steps: &Vec<usize>, steps: usize) -> usize {
     }
 }
 
+// Walk the infinitely repeated map directly. Slow for many steps, but doesn't make any assumptions about the map
+fn solve_brute((map, start): &(Vec<Vec<char>>, Coord), steps: usize) -> usize {
+    let tiled = TiledGrid::new(map);
+    let dists = bfs(&tiled, &[*start], steps, |&c| c != '#');
+    dists.values().filter(|&&d| d % 2 == steps % 2).count()
+}
+
 fn main() {
+    let ex = parse("inputs/day21_ex.txt");
+    println!("ex1: {}", solve_brute(&ex, 6));
+    println!("ex2: {}", solve_brute(&ex, 100));
+
     let inp = parse("inputs/day21.txt");
-    println!("sol1: {}", solve2b(&inp, 64));
-    println!("sol2: {}", solve2b(&inp, 26501365));
-}
\ No newline at end of file
+    println!("sol1: {}", part("sol1", || solve2b(&inp, 64)));
+    println!("sol2: {}", part("sol2", || solve2b(&inp, 26501365)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day21_ex.txt"): |f| solve_brute(&parse(f), 6) => 16;
+    ex2("inputs/day21_ex.txt"): |f| solve_brute(&parse(f), 100) => 6536;
+    sol1("inputs/day21.txt"): |f| solve2b(&parse(f), 64) => 3646;
+    sol2("inputs/day21.txt"): |f| solve2b(&parse(f), 26501365) => 606188414811259;
+    // solve2b is only correct when we end up at the edge of a block, as in the puzzle. Checking e.g. 500
+    // steps against the brute force shows that it's off otherwise.
+    brute1("inputs/day21.txt"): |f| solve_brute(&parse(f), 64) => 3646;
+    block1("inputs/day21.txt"): |f| solve2b(&parse(f), 65 + 131) => 33496;
+    block1_brute("inputs/day21.txt"): |f| solve_brute(&parse(f), 65 + 131) => 33496;
+    block4("inputs/day21.txt"): |f| solve2b(&parse(f), 65 + 4 * 131) => 300451;
+    block4_brute("inputs/day21.txt"): |f| solve_brute(&parse(f), 65 + 4 * 131) => 300451;
+}
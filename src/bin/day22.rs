@@ -1,5 +1,6 @@
 use std::{ops::Add, cmp::{min, max}};
 
+use aoc2023::stats::part;
 use itertools::Itertools;
 
 #[derive (Debug, Clone, Copy)]
@@ -133,11 +134,16 @@ fn xy_overlap(brick: Brick, bricks: Vec<&Brick>) -> Vec<(usize, &Brick)> {
 fn main() {
     let test1 = parse("inputs/day22_ex.txt");
     let testres = solve1(&test1);
-    assert!(testres.0 == 5);
-    assert!(testres.1 == 7);
+    println!("ex1: {}", testres.0);
+    println!("ex2: {}", testres.1);
 
     let inp = parse("inputs/day22.txt");
-    let res = solve1(&inp);
+    let res = part("sol1+2", || solve1(&inp));
     println!("sol1: {}", res.0);
     println!("sol2: {}", res.1);
-}
\ No newline at end of file
+}
+
+aoc2023::answers! {
+    ex("inputs/day22_ex.txt"): |f| solve1(&parse(f)) => (5, 7);
+    sol("inputs/day22.txt"): |f| solve1(&parse(f)) => (534, 88156);
+}
@@ -1,7 +1,7 @@
 // I heavily optimized the dfs search in order to make it run fast enough using edgelist and a bitmask for the visited nodes
 use std::collections::HashMap;
 
-use aoc2023::coord::{Coord, Dir, CoordMap, Bitmask};
+use aoc2023::{coord::{Coord, Dir, CoordMap, Bitmask, Notation}, dot::{self, Dot}, stats::part};
 use itertools::Itertools;
 
 fn parse(file: &str) -> Vec<Vec<char>> {
@@ -16,13 +16,17 @@ struct Path {
     end: usize,
 }
 
-fn solve(map: &Vec<Vec<char>>) -> (usize, usize) {
+// The junctions of the map, with the start as junction 0
+struct Junctions {
+    pos: Vec<Coord>,
+    non_slope_paths: Vec<Vec<Path>>,
+    all_paths: Vec<Vec<Path>>,
+}
+
+fn contract(map: &Vec<Vec<char>>) -> Junctions {
     let startx = map[0].iter().position(|&c| c == '.').unwrap();
     let start = Coord::new_u(startx, 0);
 
-    let goalx = map[map.len()-1].iter().position(|&c| c == '.').unwrap();
-    let goal = Coord::new_u(goalx, map.len()-1);
-
     let start_idx = 0;
     let mut node_idx = HashMap::new();
     node_idx.insert(start, start_idx);
@@ -38,8 +42,7 @@ fn solve(map: &Vec<Vec<char>>) -> (usize, usize) {
         let mut non_slope = vec![];
         let mut all = vec![];
 
-        let neigh = pos.neighbours().into_iter()
-            .filter(|&p| map.contains(p) && *map.at(p) != '#').collect_vec();
+        let neigh = map.neighbours(pos).filter(|&p| *map.at(p) != '#').collect_vec();
 
         for n in neigh {
             let (end, cost, has_slope) = find_path(map, pos, n);
@@ -59,15 +62,51 @@ fn solve(map: &Vec<Vec<char>>) -> (usize, usize) {
         non_slope_paths[idx] = non_slope;
         all_paths[idx] = all;
     }
-    let goal_idx = node_idx[&goal];
 
-    let mut seen = Bitmask::new(node_idx.len());
+    let mut pos = vec![start; node_idx.len()];
+    node_idx.into_iter().for_each(|(p, idx)| pos[idx] = p);
+    Junctions { pos, non_slope_paths, all_paths }
+}
+
+fn solve(map: &Vec<Vec<char>>) -> (usize, usize) {
+    let junctions = contract(map);
+    let start_idx = 0;
+
+    let goalx = map[map.len()-1].iter().position(|&c| c == '.').unwrap();
+    let goal = Coord::new_u(goalx, map.len()-1);
+    let goal_idx = junctions.pos.iter().position(|&p| p == goal).unwrap();
+
+    let mut seen = Bitmask::new(junctions.pos.len());
     seen.add(start_idx);
-    let sol1 = dfs(&non_slope_paths, start_idx, goal_idx, &mut seen, false).unwrap();
-    let sol2 = dfs(&all_paths, start_idx, goal_idx, &mut seen, true).unwrap();
+    let sol1 = dfs(&junctions.non_slope_paths, start_idx, goal_idx, &mut seen, false).unwrap();
+    let sol2 = dfs(&junctions.all_paths, start_idx, goal_idx, &mut seen, true).unwrap();
     (sol1, sol2)
 }
 
+// Junctions labelled with their position and corridors with their length. Corridors that can only
+// be walked downhill are directed.
+fn to_dot(map: &Vec<Vec<char>>) -> Dot {
+    let junctions = contract(map);
+    let mut dot = Dot::digraph("day23");
+    for (idx, p) in junctions.pos.iter().enumerate() {
+        dot.node(idx).label(format!("{},{}", p.x, p.y));
+    }
+    for (idx, paths) in junctions.all_paths.iter().enumerate() {
+        for path in paths.iter() {
+            let downhill = junctions.non_slope_paths[idx].iter().any(|p| p.end == path.end);
+            let uphill = junctions.non_slope_paths[path.end].iter().any(|p| p.end == idx);
+            if downhill && uphill {
+                if idx < path.end {
+                    dot.edge(idx, path.end).label(path.len).attr("dir", "none");
+                }
+            } else if downhill {
+                dot.edge(idx, path.end).label(path.len);
+            }
+        }
+    }
+    dot
+}
+
 fn find_path(map: &Vec<Vec<char>>, prev: Coord, start: Coord) -> (Coord, usize, bool) {
     let mut seen = 1;
     let mut prev = prev;
@@ -76,15 +115,12 @@ fn find_path(map: &Vec<Vec<char>>, prev: Coord, start: Coord) -> (Coord, usize,
 
     loop {
         let c = *map.at(pos);
-        if (c == '>' && prev == pos.go(Dir::Right)) ||
-            (c == '<' && prev == pos.go(Dir::Left)) ||
-            (c == 'v' && prev == pos.go(Dir::Down)) ||
-            (c == '^' && prev == pos.go(Dir::Up))
-        {
+        // going up the slope
+        if Dir::from_char(c, Notation::Arrow).is_some_and(|d| prev == pos.go(d)) {
             slope = true;
         }
-        let neigh = pos.neighbours().into_iter()
-            .filter(|&p| map.contains(p) && *map.at(p) != '#')
+        let neigh = map.neighbours(pos)
+            .filter(|&p| *map.at(p) != '#')
             .filter(|&p| p != prev).collect_vec();
         if neigh.len() != 1 {
             return (pos, seen, slope)
@@ -118,11 +154,17 @@ fn dfs(paths: &Vec<Vec<Path>>, start: usize, end: usize, seen: &mut Bitmask, ign
 fn main() {
     let test = parse("inputs/day23_ex1.txt");
     let (test1, test2) = solve(&test);
-    assert!(test1 == 94);
-    assert!(test2 == 154);
+    println!("ex1: {}", test1);
+    println!("ex2: {}", test2);
 
     let inp = parse("inputs/day23.txt");
-    let (sol1, sol2) = solve(&inp);
+    dot::export("day23", || to_dot(&inp));
+    let (sol1, sol2) = part("sol1+2", || solve(&inp));
     println!("sol1: {}", sol1);
     println!("sol2: {}", sol2);
-}
\ No newline at end of file
+}
+
+aoc2023::answers! {
+    ex("inputs/day23_ex1.txt"): |f| solve(&parse(f)) => (94, 154);
+    sol("inputs/day23.txt"): |f| solve(&parse(f)) => (2094, 6442);
+}
@@ -1,15 +1,13 @@
+use aoc2023::{geometry::{intersect2, Line, Rational}, linalg::solve, parallel::par_map, stats::part};
 use itertools::Itertools;
-use num::ToPrimitive;
-use priority_queue::DoublePriorityQueue;
-use std::{ops::{Mul, Add, Sub}, cmp::min};
+use num::{BigInt, BigRational, Signed, ToPrimitive};
+use std::ops::Sub;
 
 
-type C3 = (f64, f64, f64);
-type C2 = (f64, f64);
 type I3 = (i64, i64, i64);
 
 
-fn parse(file: &str) -> Vec<(C3, C3)> {
+fn parse(file: &str) -> Vec<(I3, I3)> {
     std::fs::read_to_string(file).unwrap().trim().lines().map(|line| {
         let mut parts = line.split(" @ ");
         let pos = parse_coord(parts.next().unwrap());
@@ -18,9 +16,9 @@ fn parse(file: &str) -> Vec<(C3, C3)> {
     }).collect_vec()
 }
 
-fn parse_coord(s: &str) -> C3 {
+fn parse_coord(s: &str) -> I3 {
     if let Some(coord) = s.split(", ").map(|c| {
-        c.trim().parse::<f64>().unwrap()
+        c.trim().parse::<i64>().unwrap()
     }).collect_tuple() {
         coord
     } else {
@@ -28,229 +26,82 @@ fn parse_coord(s: &str) -> C3 {
     }
 }
 
-fn solve1(stones: &Vec<(C3, C3)>, start: f64, end: f64) -> usize {
-    let eqs = stones.iter().map(|(pos, dest)| {
-        let k = f64::from(dest.1) / f64::from(dest.0);
-        let m = f64::from(pos.1) - f64::from(pos.0) * k;
-        (k, m)
-    }).collect_vec();
-
-    let mut cnt = 0;
-    for i in 0..eqs.len() {
-        for j in 0..i {
-            if i != j {
-                let (p1, dp1) = stones[i];
-                let (p2, dp2) = stones[j];
-                let p1 = proj_xy(p1);
-                let dp1 = proj_xy(dp1);
-                let p2 = proj_xy(p2);
-                let dp2 = proj_xy(dp2);
-                let t = intersect(p1, dp1, p2, dp2);
-                let u = intersect(p2, dp2, p1, dp1);
-                if t == None || u == None {
-                    continue;
-                }
-                let t = t.unwrap();
-                let u = u.unwrap();
-                if t < 0.0 || u < 0.0 {
-                    continue;
-                }
-                let p = (p1.0 + dp1.0 * t, p1.1 + dp1.1 * t);
-                if p.0 < start || p.1 < start || p.0 > end || p.1 > end {
-                    continue;
-                }
-                cnt += 1;
-            }
+fn solve1(stones: &Vec<(I3, I3)>, start: i64, end: i64) -> usize {
+    let lines = stones.iter().map(|&((x, y, _), (dx, dy, _))| Line::new([x, y], [dx, dy])).collect_vec();
+    let in_area = |v: &Rational| *v >= Rational::from(start as i128) && *v <= Rational::from(end as i128);
+
+    let idxs = (0..lines.len()).collect_vec();
+    par_map(&idxs, |&i| {
+        (0..i).filter(|&j| {
+            intersect2(&lines[i], &lines[j]).is_some_and(|is| {
+                !is.t.is_negative() && !is.u.is_negative() && is.point.iter().all(in_area)
+            })
+        }).count()
+    }).into_iter().sum()
+}
+
+// The rock P + t * V hits stone i when P + t * V = p_i + t * v_i, that is when (P - p_i) x (V - v_i) = 0.
+// The P x V term is the same for all stones, so subtracting the equations of two stones gives
+//   P x (v_j - v_i) + (p_j - p_i) x V = p_j x v_j - p_i x v_i
+// which is linear in P and V. Three pairs of stones give more than enough equations.
+fn solve2(stones: &Vec<(I3, I3)>) -> i64 {
+    let mut a = vec![];
+    let mut b = vec![];
+    let (p0, v0) = stones[0];
+    for &(p, v) in stones[1..4].iter() {
+        let m_p = cross_matrix(sub3(v0, v)); // P x (v - v0) = (v0 - v) x P
+        let m_v = cross_matrix(sub3(p, p0));
+        let rhs = sub3(cross(p, v), cross(p0, v0));
+        for (k, r) in [rhs.0, rhs.1, rhs.2].into_iter().enumerate() {
+            a.push(m_p[k].iter().chain(m_v[k].iter()).map(|&x| BigRational::from(BigInt::from(x))).collect_vec());
+            b.push(BigRational::from(BigInt::from(r)));
         }
     }
-    cnt
-}
 
-fn proj_xy<T>((a, b, _): (T, T, T)) -> (T, T) {
-    (a, b)
+    let sol = solve(&a, &b).expect("No rock hits all stones");
+    assert!(sol.free.is_empty(), "The rock's path is not unique");
+    sol.values[0..3].iter().map(|x| {
+        assert!(x.is_integer(), "The rock starts at a non-integer position {x}");
+        x.to_integer().to_i64().unwrap()
+    }).sum()
 }
 
-fn intersect((x1, y1): (f64, f64), (dx1, dy1): (f64, f64), (x2, y2): (f64, f64), (dx2, dy2): (f64, f64)) -> Option<f64> {
-    let den = dx1 * dy2 - dy1 * dx2;
-    if den == 0.0 {
-        return None;
-    }
-    let nom = (x2 - x1) * dy2 - (y2 - y1) * dx2;
-    Some(nom / den)
-}
-
-// Project the lines onto a plane for a given normal
-// Search for normal that minimises distace beteen intersection points
-// When all lines intersect at the same point we have found the direction of our throw
-fn solve2(inp: &Vec<(C3, C3)>) -> i64 {
-    let n1: I3 = (0, 0, 0);
-    let mut processed = vec![n1];
-    let mut queue = DoublePriorityQueue::new();
-    queue.push(n1, i64::MAX);
-
-    // We don't need all lines, just a few
-    let inp = inp.iter().map(|&x| x).take(7).collect_vec();
-
-    let epsilon: f64 = 0.5;
-    while !queue.is_empty() {
-        let (normal, _) = queue.pop_min().unwrap();
-
-        let neigh = neighbours(normal);
-        for n in neigh {
-            // ignore negative z axis
-            if processed.contains(&n) || n.2 < 0 {
-                continue;
-            }
-
-            let projected = proj_all(&inp, norm(n));
-            let dist = diam(&projected);
-            let score = (dist + (1.0 - epsilon)).to_i64().unwrap_or(i64::MAX);
-
-            if score == 0 {
-                println!("found normal! {n:?}");
-
-                let t0 = intersect(projected[0].0, projected[0].1, projected[1].0, projected[1].1).unwrap().round();
-                let t1 = intersect(projected[1].0, projected[1].1, projected[0].0, projected[0].1).unwrap().round();
-                let p0 = add3(inp[0].0, mult3(inp[0].1, t0));
-                let p1 = add3(inp[1].0, mult3(inp[1].1, t1));
-
-                // p0 and p1 are on the line we are looking for
-                let dt = t1 - t0;
-                let dir = mult3(sub3(p1, p0), 1.0/dt);
-                let start_pos = sub3(p0, mult3(dir, t0));
-                let res = start_pos.0.round() + start_pos.1.round() + start_pos.2.round();
-                return res.to_i64().unwrap();
-            }
-            queue.push(n, score);
-            processed.push(n);
-        }
-    }
-    panic!("Unexpected: did not find a solution");
-}
-
-fn norm(n: I3) -> C3 {
-    let n2 = (n.0 as f64, n.1 as f64, n.2 as f64);
-    normalize(n2)
-}
-
-fn mult3<T>(v: (T, T, T), c: T) -> (T, T, T) where T: Mul<Output = T>, T: Copy {
-    (v.0 * c, v.1 * c, v.2 * c)
-}
-
-fn add3<T>(v: (T, T, T), u: (T, T, T)) -> (T, T, T) where T: Add<Output = T> {
-    (v.0 + u.0 , v.1 + u.1, v.2 + u.2)
+// The matrix m with m * w = v x w
+fn cross_matrix((x, y, z): I3) -> [[i64; 3]; 3] {
+    [
+        [0, -z, y],
+        [z, 0, -x],
+        [-y, x, 0],
+    ]
 }
 
 fn sub3<T>(v: (T, T, T), u: (T, T, T)) -> (T, T, T) where T: Sub<Output = T> {
     (v.0 - u.0 , v.1 - u.1, v.2 - u.2)
 }
 
-fn normalize(v: C3) -> C3 {
-    let d = c3_len(v);
-    (v.0 / d, v.1 / d, v.2 / d)
-}
-
-fn c3_len(v: C3) -> f64 {
-    (v.0 * v.0 + v.1 * v.1 + v.2 * v.2).sqrt()
-}
-
-fn neighbours(v: I3) -> Vec<I3> {
-    let w = 1;
-    (-w..=w).flat_map(|x| 
-        (-w..=w).flat_map(move |y| 
-            (-w..=w).map(move |z|
-                add3(v, (x, y, z)
-            )
-        )
-    )).filter(|&u| u != v).collect_vec()
-}
-
-fn diam(stones: &Vec<(C2, C2)>) -> f64 {
-    let res = intersections(&stones[0..stones.len()]);
-    let mut max_dist = 0.0;
-    for i in 0..res.len() {
-        for j in 0..i {
-            let d = dist(res[i], res[j]);
-            if d > max_dist {
-                max_dist = d;
-            }
-        }
-    }
-    return max_dist;
-}
-
-fn intersections(stones: &[(C2, C2)]) -> Vec<C2> {
-    let mut results = vec![];
-    for i in 0..stones.len() {
-        for j in 0..min(i, 3) {
-            let (p1, dp1) = stones[i];
-            let (p2, dp2) = stones[j];
-            let t = intersect(p1, dp1, p2, dp2);
-            let u = intersect(p2, dp2, p1, dp1);
-            if t == None || u == None{
-                // parallell
-                continue;
-            }
-            let t = t.unwrap();
-            let u = u.unwrap();
-            if t < 0.0 {
-                results.push(p1);
-            }
-            if u < 0.0 {
-                results.push(p1);
-            }
-            if u >= 0.0 && t >= 0.0 {
-                let p = (p1.0 + dp1.0 * t, p1.1 + dp1.1 * t);
-                results.push(p);
-            }
-        }
-    }
-    results
-}
-
-fn dist(a: C2, b: C2) -> f64 {
-    ((a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)).sqrt()
-}
-
-fn proj_all(inp: &Vec<(C3, C3)>, normal: C3) -> Vec<(C2, C2)> {
-    let e1 = normalize(orth(normal));
-    let e2 = normalize(cross(normal, e1));
-    inp.iter().map(|(v, d)| (proj_e(*v, e1, e2), proj_e(*d, e1, e2))).collect_vec()
-}
-
-    
-fn proj_e(x: C3, e1: C3, e2: C3) -> C2 {
-    (dot(x, e1), dot(x, e2))
-}
-
-fn orth(a: C3) -> C3 {
-    if a.0 != 0.0 || a.1 != 0.0 {
-        (-a.1, a.0, 0.0)
-    } else {
-        (-a.2, 0.0, a.1)
-    }
-}
-
-fn cross(a: C3, b: C3) -> C3 {
+fn cross(a: I3, b: I3) -> I3 {
     let x = a.1 * b.2 - a.2 * b.1;
     let y = a.2 * b.0 - a.0 * b.2;
     let z = a.0 * b.1 - a.1 * b.0;
     (x, y, z)
 }
 
-fn dot(a: C3, b: C3) -> f64 {
-    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
-}
-
 fn main() {
-    let a = 200000000000000.0;
-    let b = 400000000000000.0;
+    let a = 200000000000000;
+    let b = 400000000000000;
     let test = parse("inputs/day24_ex.txt");
     let inp = parse("inputs/day24.txt");
 
-    println!("example1: {}", solve1(&test, 7.0, 27.0));
-    println!("sol1: {}", solve1(&inp, a, b));
+    println!("example1: {}", solve1(&test, 7, 27));
+    println!("sol1: {}", part("sol1", || solve1(&inp, a, b)));
 
     println!("example2: {}", solve2(&test));
-    println!("sol2: {}", solve2(&inp));
-}
\ No newline at end of file
+    println!("sol2: {}", part("sol2", || solve2(&inp)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day24_ex.txt"): |f| solve1(&parse(f), 7, 27) => 2;
+    sol1("inputs/day24.txt"): |f| solve1(&parse(f), 200000000000000, 400000000000000) => 23760;
+    ex2("inputs/day24_ex.txt"): |f| solve2(&parse(f)) => 47;
+    sol2("inputs/day24.txt"): |f| solve2(&parse(f)) => 888708704663413;
+}
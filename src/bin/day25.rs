@@ -1,38 +1,87 @@
+use std::env;
+
+use aoc2023::{dot::{self, Dot}, graph::{min_st_cut, stoer_wagner, unit_edges, Cut, NamedGraph}, parallel::{self, par_map}, stats::part};
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-fn parse(file: &str) -> Vec<Vec<usize>> {
-    let mut names: Vec<String> = vec![];
-    let mut edge_list = vec![];
+enum Mode {
+    StoerWagner,
+    Flow,
+    Karger(u64),
+}
 
-    std::fs::read_to_string(file).unwrap().trim().lines().for_each(|line| {
-        let mut parts = line.split(": ");
-        let lhs = parts.next().unwrap().to_string();
-        if !names.contains(&lhs) {
-            names.push(lhs.to_string());
-            edge_list.push(vec![]);
-        }
-        let lhs_idx = names.iter().position(|n| n == &lhs).unwrap();
-        for rhs in parts.next().unwrap().split(' ') {
-            if !names.contains(&rhs.to_string()) {
-                names.push(rhs.to_string());
-                edge_list.push(vec![]);
+// Usage: day25 [--flow | --karger [SEED]] [--dot DIR]
+fn parse_mode() -> Mode {
+    let mut mode = Mode::StoerWagner;
+    let mut args = env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--flow" => mode = Mode::Flow,
+            "--karger" => {
+                let seed = args.next_if(|a| !a.starts_with("--"))
+                    .map_or_else(rand::random, |seed| seed.parse().expect("Seed should be a number"));
+                mode = Mode::Karger(seed);
             }
+            "--dot" => {
+                args.next();
+            }
+            _ => panic!("usage: day25 [--flow | --karger [SEED]] [--dot DIR]"),
+        }
+    }
+    mode
+}
 
-            let rhs_idx = names.iter().position(|n| n == rhs).unwrap();
-            edge_list[lhs_idx].push(rhs_idx);
-            edge_list[rhs_idx].push(lhs_idx);
+fn parse(file: &str) -> NamedGraph {
+    let mut graph = NamedGraph::new();
+    std::fs::read_to_string(file).unwrap().trim().lines().for_each(|line| {
+        let (lhs, rhs) = line.split_once(": ").unwrap();
+        for rhs in rhs.split(' ') {
+            graph.add_undirected(lhs, rhs);
         }
     });
-    edge_list
+    graph
+}
+
+fn group_product(cut: &Cut) -> usize {
+    assert_eq!(3, cut.weight, "Expected to cut exactly three wires");
+    cut.partition.0.len() * cut.partition.1.len()
+}
+
+// The product of the group sizes, and the wires to cut
+fn solve(graph: &NamedGraph, mode: &Mode) -> (usize, Vec<String>) {
+    let cut = match mode {
+        Mode::StoerWagner => solve1(&graph.adj),
+        Mode::Flow => solve1_flow(&graph.adj),
+        Mode::Karger(seed) => solve1_karger(&graph.adj, *seed),
+    };
+    let wires = cut.edges.iter().map(|&(a, b)| format!("{}/{}", graph.name(a), graph.name(b))).sorted().collect();
+    (group_product(&cut), wires)
+}
+
+fn solve1(adj: &[Vec<usize>]) -> Cut {
+    stoer_wagner(adj.len(), &unit_edges(adj))
+}
+
+// The wires to cut separate node 0 from some other node, find it with max flow
+fn solve1_flow(adj: &[Vec<usize>]) -> Cut {
+    let edges = unit_edges(adj);
+    (1..adj.len()).map(|t| min_st_cut(adj.len(), &edges, 0, t)).find(|cut| cut.weight == 3).unwrap()
 }
 
-fn solve1(edges: &Vec<Vec<usize>>) -> usize {
-    loop {
-        let (edges, res) = karger(edges);
-        if edges == 3 {
-            return res;
+// Run batches of karger trials in parallel until one of them finds the 3-edge cut.
+// Trial i is seeded with seed + i, so a given seed always gives the same trials.
+fn solve1_karger(adj: &[Vec<usize>], seed: u64) -> Cut {
+    let batch = parallel::threads() as u64;
+    for start in (0..).step_by(batch as usize) {
+        let trials = (start..start + batch).collect_vec();
+        let found = par_map(&trials, |&i| {
+            karger(adj, &mut StdRng::seed_from_u64(seed.wrapping_add(i)))
+        }).into_iter().find(|cut| cut.weight == 3);
+        if let Some(cut) = found {
+            return cut;
         }
     }
+    unreachable!()
 }
 
 struct Subset {
@@ -40,16 +89,14 @@ struct Subset {
     rank: usize,
 }
 
-fn karger(adj_list: &Vec<Vec<usize>>) -> (usize, usize) {
-    let edges: Vec<(usize, usize)> = adj_list.iter().enumerate().flat_map(|(i, conns)| {
-        conns.iter().filter_map(move |&j| if i < j { Some((i, j)) } else { None })
-    }).collect();
+fn karger(adj_list: &[Vec<usize>], rng: &mut StdRng) -> Cut {
+    let edges = unit_edges(adj_list);
 
     let mut subsets = (0..adj_list.len()).map(|n| Subset { parent: n, rank: 0}).collect_vec();
     let mut num_vertices = subsets.len();
 
     while num_vertices > 2 {
-        let i = rand::random::<usize>() % edges.len();
+        let i = rng.gen_range(0..edges.len());
         let subset1 = find(&mut subsets, edges[i].0);
         let subset2 = find(&mut subsets, edges[i].1);
 
@@ -60,17 +107,9 @@ fn karger(adj_list: &Vec<Vec<usize>>) -> (usize, usize) {
         mk_union(&mut subsets, subset1, subset2);
     }
 
-    let mut cutedges = 0;
-    for edge in edges {
-        let subset1 = find(&mut subsets, edge.0);
-        let subset2 = find(&mut subsets, edge.1);
-        if subset1 != subset2 {
-            cutedges += 1;
-        }
-    }
-
-    let cnt = subsets.iter().filter(|s| s.parent == subsets[0].parent).count();
-    (cutedges, cnt * (subsets.len() - cnt))
+    let root = find(&mut subsets, 0);
+    let in_first = (0..subsets.len()).map(|i| find(&mut subsets, i) == root).collect_vec();
+    Cut::from_sides(adj_list.len(), &edges, &in_first)
 }
 
 fn find(subsets: &mut Vec<Subset>, i: usize) -> usize {
@@ -86,7 +125,7 @@ fn mk_union(subsets: &mut Vec<Subset>, x: usize, y: usize) {
 
     if subsets[xroot].rank < subsets[yroot].rank {
         subsets[xroot].parent = yroot;
-    } else if subsets[xroot].rank < subsets[yroot].rank {
+    } else if subsets[xroot].rank > subsets[yroot].rank {
         subsets[yroot].parent = xroot;
     } else {
         subsets[yroot].parent = xroot;
@@ -94,10 +133,40 @@ fn mk_union(subsets: &mut Vec<Subset>, x: usize, y: usize) {
     }
 }
 
+// The wiring diagram with the three wires to cut in red
+fn to_dot(graph: &NamedGraph) -> Dot {
+    let edges = unit_edges(&graph.adj);
+    let cut = stoer_wagner(graph.len(), &edges);
+    let mut dot = Dot::graph("day25");
+    for (a, b, _) in edges {
+        let edge = dot.edge(graph.name(a), graph.name(b));
+        if cut.edges.contains(&(a, b)) {
+            edge.attr("color", "red").attr("penwidth", 3);
+        }
+    }
+    dot
+}
+
 fn main() {
+    let mode = parse_mode();
+
     let test = parse("inputs/day25_ex.txt");
-    println!("example1: {}", solve1(&test));
+    let (test1, wires) = solve(&test, &mode);
+    println!("example1: {}", test1);
+    println!("example1 wires: {}", wires.join(", "));
 
     let inp = parse("inputs/day25.txt");
-    println!("sol1: {}", solve1(&inp));
-}
\ No newline at end of file
+    dot::export("day25", || to_dot(&inp));
+    let (sol1, wires) = part("sol1", || solve(&inp, &mode));
+    println!("sol1: {}", sol1);
+    println!("sol1 wires: {}", wires.join(", "));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day25_ex.txt"): |f| solve(&parse(f), &Mode::StoerWagner).0 => 54;
+    ex1_wires("inputs/day25_ex.txt"): |f| solve(&parse(f), &Mode::StoerWagner).1 => vec!["cmg/bvb", "jqt/nvd", "pzl/hfx"];
+    ex1_flow("inputs/day25_ex.txt"): |f| solve(&parse(f), &Mode::Flow).0 => 54;
+    ex1_karger("inputs/day25_ex.txt"): |f| solve(&parse(f), &Mode::Karger(1)).0 => 54;
+    sol1("inputs/day25.txt"): |f| solve(&parse(f), &Mode::StoerWagner).0 => 614655;
+    sol1_flow("inputs/day25.txt"): |f| solve(&parse(f), &Mode::Flow).0 => 614655;
+}
@@ -1,49 +1,120 @@
-use std::fs;
+use std::{collections::{BTreeMap, BTreeSet}, env, fs};
+
+use aoc2023::{coord::Coord, grid::SparseGrid, stats::part};
 
 #[derive (Debug)]
 struct Number {
     n: i32,
-    row: i32,
-    col: i32,
-    len: i32,
+    pos: Coord, // position of the first digit
+    len: i64,
+    digits: String, // as written, leading zeros included
 }
 
 impl Number {
-    fn new(row: i32, col: i32) -> Self {
-        Number { n: 0, row, col, len: 0}
+    fn new(pos: Coord) -> Self {
+        Number { n: 0, pos, len: 0, digits: String::new() }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.len).map(|i| self.pos + Coord::new(i, 0))
     }
 }
 
 #[derive (Debug)]
 struct Symbol {
-    row: i32,
-    col: i32,
+    pos: Coord,
     c: char,
 }
 
-fn adjacent(n: &Number, s: &Symbol) -> bool {
-    let far = (n.row - s.row).abs() > 1 ||
-      s.col < n.col - 1 ||
-      s.col > n.col + n.len;
-    !far
+// What occupies a cell of the schematic, as an index into the numbers or symbols
+#[derive (Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Empty,
+    Number(usize),
+    Symbol(usize),
+}
+
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    cells: SparseGrid<Cell>,
 }
 
-fn parse(filename: &str) -> (Vec<Number>, Vec<Symbol>) {
+impl Schematic {
+    fn new(numbers: Vec<Number>, symbols: Vec<Symbol>) -> Self {
+        let mut cells = SparseGrid::new(Cell::Empty);
+        for (i, num) in numbers.iter().enumerate() {
+            num.cells().for_each(|p| cells.set(p, Cell::Number(i)));
+        }
+        for (i, sym) in symbols.iter().enumerate() {
+            cells.set(sym.pos, Cell::Symbol(i));
+        }
+        Schematic { numbers, symbols, cells }
+    }
+
+    // The numbers touching the symbol, each once, in the order they were parsed
+    fn numbers_near(&self, sym: &Symbol) -> Vec<&Number> {
+        let indices: BTreeSet<usize> = sym.pos.neighbours8().filter_map(|p| match self.cells.get(p) {
+            Cell::Number(i) => Some(*i),
+            _ => None,
+        }).collect();
+        indices.into_iter().map(|i| &self.numbers[i]).collect()
+    }
+
+    fn symbols_near(&self, num: &Number) -> Vec<&Symbol> {
+        let indices: BTreeSet<usize> = num.cells().flat_map(|p| p.neighbours8()).filter_map(|p| match self.cells.get(p) {
+            Cell::Symbol(i) => Some(*i),
+            _ => None,
+        }).collect();
+        indices.into_iter().map(|i| &self.symbols[i]).collect()
+    }
+
+    fn parts(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().filter(|num| !self.symbols_near(num).is_empty())
+    }
+
+    // The character drawn at p, '.' for empty cells
+    fn char_at(&self, p: Coord) -> char {
+        match *self.cells.get(p) {
+            Cell::Empty => '.',
+            Cell::Symbol(i) => self.symbols[i].c,
+            Cell::Number(i) => {
+                let num = &self.numbers[i];
+                num.digits.as_bytes()[(p.x - num.pos.x) as usize] as char
+            }
+        }
+    }
+
+    // The symbols drawn with `c` that touch exactly k numbers, with those numbers
+    fn gears(&self, c: char, k: usize) -> Vec<(&Symbol, Vec<&Number>)> {
+        self.symbols.iter()
+            .filter(|sym| sym.c == c)
+            .map(|sym| (sym, self.numbers_near(sym)))
+            .filter(|(_, nums)| nums.len() == k)
+            .collect()
+    }
+}
+
+fn parse(filename: &str) -> Schematic {
+    parse_schematic(&fs::read_to_string(filename).unwrap())
+}
+
+fn parse_schematic(content: &str) -> Schematic {
     let mut numbers = vec![];
     let mut symbols = vec![];
 
-    let content = fs::read_to_string(filename).unwrap();
     let rows = content.trim().lines();
-    (0i32..).zip(rows).for_each(|(j, row)| {
+    (0i64..).zip(rows).for_each(|(j, row)| {
         let mut current: Option<Number> = None;
-        for (i, c) in (0i32..).zip(row.chars()) {
+        for (i, c) in (0i64..).zip(row.chars()) {
             if let Some(digit) = c.to_digit(10)  {
                 if current.is_none() {
-                    current = Some(Number::new(j, i));
+                    current = Some(Number::new(Coord::new(i, j)));
                 }
                 current.iter_mut().for_each(|num| {
                     num.n = num.n * 10 + digit as i32;
                     num.len += 1;
+                    num.digits.push(c);
                 })
             } else {
                 // Push if we have a current number
@@ -53,7 +124,7 @@ fn parse(filename: &str) -> (Vec<Number>, Vec<Symbol>) {
                 }
 
                 if c != '.' {
-                    symbols.push(Symbol {row: j, col: i, c: c})
+                    symbols.push(Symbol {pos: Coord::new(i, j), c: c})
                 }
             }
         }
@@ -61,31 +132,201 @@ fn parse(filename: &str) -> (Vec<Number>, Vec<Symbol>) {
             numbers.push(num);
         }
     });
-    (numbers, symbols)
+    Schematic::new(numbers, symbols)
+}
+
+struct Report<'a> {
+    parts: Vec<(&'a Number, Vec<&'a Symbol>)>,
+    dangling: Vec<&'a Number>, // numbers touching no symbol
+    lonely: Vec<&'a Symbol>,   // symbols touching no number
+}
+
+fn report(schematic: &Schematic) -> Report<'_> {
+    let mut parts = vec![];
+    let mut dangling = vec![];
+    for num in schematic.numbers.iter() {
+        let syms = schematic.symbols_near(num);
+        if syms.is_empty() {
+            dangling.push(num);
+        } else {
+            parts.push((num, syms));
+        }
+    }
+    let lonely = schematic.symbols.iter().filter(|sym| schematic.numbers_near(sym).is_empty()).collect();
+    Report { parts, dangling, lonely }
+}
+
+fn print_report(schematic: &Schematic) {
+    let report = report(schematic);
+    let symbols: BTreeSet<char> = schematic.symbols.iter().map(|sym| sym.c).collect();
+    println!("symbols: {}", symbols.iter().collect::<String>());
+    for (num, syms) in report.parts.iter() {
+        let syms = syms.iter().map(|sym| format!("{} at {},{}", sym.c, sym.pos.x, sym.pos.y)).collect::<Vec<_>>();
+        println!("part {} at {},{}: {}", num.n, num.pos.x, num.pos.y, syms.join(", "));
+    }
+    for num in report.dangling.iter() {
+        println!("dangling number {} at {},{}", num.n, num.pos.x, num.pos.y);
+    }
+    for sym in report.lonely.iter() {
+        println!("lonely symbol {} at {},{}", sym.c, sym.pos.x, sym.pos.y);
+    }
 }
 
-fn solve1((numbers, symbols): &(Vec<Number>, Vec<Symbol>)) -> i32 {
-    numbers.iter().filter(|num| {
-        symbols.iter().any(|sym| adjacent(num, sym))
-    }).map(|number| number.n).sum()
+#[derive (Debug)]
+struct Diff {
+    cells: Vec<(Coord, char, char)>,
+    removed: Vec<(Coord, i32)>, // part numbers only in the first schematic
+    added: Vec<(Coord, i32)>,
+    gears: Vec<(Coord, Option<i32>, Option<i32>)>, // gear ratios of '*' by position, before and after
+}
+
+fn diff(a: &Schematic, b: &Schematic) -> Diff {
+    let positions: BTreeSet<(i64, i64)> = a.cells.iter().chain(b.cells.iter()).map(|(p, _)| (p.y, p.x)).collect();
+    let cells = positions.into_iter()
+        .map(|(y, x)| Coord::new(x, y))
+        .map(|p| (p, a.char_at(p), b.char_at(p)))
+        .filter(|(_, ca, cb)| ca != cb)
+        .collect();
+
+    let parts = |s: &Schematic| s.parts().map(|num| (num.pos, num.n)).collect::<Vec<_>>();
+    let (parts_a, parts_b) = (parts(a), parts(b));
+    let removed = parts_a.iter().filter(|part| !parts_b.contains(part)).copied().collect();
+    let added = parts_b.iter().filter(|part| !parts_a.contains(part)).copied().collect();
+
+    let ratios = |s: &Schematic| s.gears('*', 2).iter()
+        .map(|(sym, nums)| ((sym.pos.y, sym.pos.x), nums[0].n * nums[1].n))
+        .collect::<BTreeMap<_, _>>();
+    let (ratios_a, ratios_b) = (ratios(a), ratios(b));
+    let gear_positions: BTreeSet<_> = ratios_a.keys().chain(ratios_b.keys()).collect();
+    let gears = gear_positions.into_iter()
+        .map(|p| (Coord::new(p.1, p.0), ratios_a.get(p).copied(), ratios_b.get(p).copied()))
+        .filter(|(_, ra, rb)| ra != rb)
+        .collect();
+
+    Diff { cells, removed, added, gears }
+}
+
+fn print_diff(a: &Schematic, b: &Schematic) {
+    let diff = diff(a, b);
+    let fmt_ratio = |r: Option<i32>| r.map_or("-".to_string(), |r| r.to_string());
+    for (p, ca, cb) in diff.cells.iter() {
+        println!("cell {},{}: {ca} -> {cb}", p.x, p.y);
+    }
+    for (p, n) in diff.removed.iter() {
+        println!("- part {n} at {},{}", p.x, p.y);
+    }
+    for (p, n) in diff.added.iter() {
+        println!("+ part {n} at {},{}", p.x, p.y);
+    }
+    for (p, ra, rb) in diff.gears.iter() {
+        println!("gear at {},{}: {} -> {}", p.x, p.y, fmt_ratio(*ra), fmt_ratio(*rb));
+    }
 }
 
-fn solve2((numbers, symbols): &(Vec<Number>, Vec<Symbol>)) -> i32 {
-    symbols.iter()
-        .filter(|sym| sym.c == '*')
-        .filter_map(|sym| {
-            let adj_nums: Vec<&Number> = numbers.iter().filter(|num| adjacent(num, sym)).collect();
-            (adj_nums.len() == 2).then(|| adj_nums[0].n * adj_nums[1].n)
-        }).sum()
+fn solve1(schematic: &Schematic) -> i32 {
+    schematic.parts().map(|number| number.n).sum()
 }
 
+fn solve2(schematic: &Schematic) -> i32 {
+    schematic.gears('*', 2).iter()
+        .map(|(_, nums)| nums.iter().map(|num| num.n).product::<i32>())
+        .sum()
+}
+
+enum Mode {
+    Solve,
+    Report(String),
+    Diff(String, String),
+}
+
+fn parse_args() -> Mode {
+    let mut mode = Mode::Solve;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{arg} needs a value"));
+        match arg.as_str() {
+            "--report" => mode = Mode::Report(value()),
+            "--diff" => mode = Mode::Diff(value(), value()),
+            _ => panic!("Unknown argument {arg}"),
+        }
+    }
+    mode
+}
 
 fn main() {
+    match parse_args() {
+        Mode::Solve => {}
+        Mode::Report(file) => return print_report(&parse(&file)),
+        Mode::Diff(a, b) => return print_diff(&parse(&a), &parse(&b)),
+    }
+
     let example1 = parse("inputs/day3_ex1.txt");
     let input = parse("inputs/day3.txt");
 
     println!("example1: {}", solve1(&example1));
-    println!("solution1: {}", solve1(&input));
+    println!("solution1: {}", part("sol1", || solve1(&input)));
     println!("example2: {}", solve2(&example1));
-    println!("solution2: {}", solve2(&input));
-}
\ No newline at end of file
+    println!("solution2: {}", part("sol2", || solve2(&input)));
+}
+
+#[test]
+fn test_index() {
+    let schematic = parse("inputs/day3_ex1.txt");
+    let star = &schematic.symbols[0];
+    assert_eq!(('*', Coord::new(3, 1)), (star.c, star.pos));
+    assert_eq!(vec![467, 35], schematic.numbers_near(star).iter().map(|num| num.n).collect::<Vec<_>>());
+
+    let n617 = schematic.numbers.iter().find(|num| num.n == 617).unwrap();
+    assert_eq!(vec!['*'], schematic.symbols_near(n617).iter().map(|sym| sym.c).collect::<Vec<_>>());
+    let n114 = schematic.numbers.iter().find(|num| num.n == 114).unwrap();
+    assert!(schematic.symbols_near(n114).is_empty());
+
+    assert_eq!(1, schematic.gears('*', 1).len());
+    assert_eq!(2, schematic.gears('*', 2).len());
+    assert_eq!(vec![592], schematic.gears('+', 1)[0].1.iter().map(|num| num.n).collect::<Vec<_>>());
+    assert!(schematic.gears('#', 3).is_empty());
+}
+
+#[test]
+fn test_report() {
+    let schematic = parse("inputs/day3_ex1.txt");
+    let found = report(&schematic);
+    assert_eq!(8, found.parts.len());
+    assert_eq!(vec![114, 58], found.dangling.iter().map(|num| num.n).collect::<Vec<_>>());
+    assert!(found.lonely.is_empty());
+
+    let schematic = parse_schematic("1.\n..\n.#");
+    let found = report(&schematic);
+    assert_eq!(vec![1], found.dangling.iter().map(|num| num.n).collect::<Vec<_>>());
+    assert_eq!(vec!['#'], found.lonely.iter().map(|sym| sym.c).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_diff() {
+    let a = parse("inputs/day3_ex1.txt");
+    let content = fs::read_to_string("inputs/day3_ex1.txt").unwrap();
+    // 35 becomes 36 and the # next to 633 is removed
+    let b = parse_schematic(&content.replace("..35..633.", "..36..633.").replace("......#...", ".........."));
+    let changes = diff(&a, &b);
+    assert_eq!(vec![(Coord::new(3, 2), '5', '6'), (Coord::new(6, 3), '#', '.')], changes.cells);
+    assert_eq!(vec![(Coord::new(2, 2), 35), (Coord::new(6, 2), 633)], changes.removed);
+    assert_eq!(vec![(Coord::new(2, 2), 36)], changes.added);
+    assert_eq!(vec![(Coord::new(3, 1), Some(16345), Some(16812))], changes.gears);
+
+    assert!(diff(&a, &a).cells.is_empty());
+
+    // leading zeros take up cells too
+    let a = parse_schematic("007*.\n....2");
+    let b = parse_schematic("008*.\n....2");
+    let changes = diff(&a, &b);
+    assert_eq!(vec![(Coord::new(2, 0), '7', '8')], changes.cells);
+    assert_eq!(vec![(Coord::new(0, 0), 7)], changes.removed);
+    assert_eq!(vec![(Coord::new(3, 0), Some(14), Some(16))], changes.gears);
+}
+
+aoc2023::answers! {
+    ex1("inputs/day3_ex1.txt"): |f| solve1(&parse(f)) => 4361;
+    sol1("inputs/day3.txt"): |f| solve1(&parse(f)) => 537732;
+    ex2("inputs/day3_ex1.txt"): |f| solve2(&parse(f)) => 467835;
+    sol2("inputs/day3.txt"): |f| solve2(&parse(f)) => 84883664;
+}
@@ -1,4 +1,6 @@
-use std::fs;
+use std::{env, fmt, fs};
+
+use aoc2023::stats::part;
 use winnow::{
     ascii::digit1,
     combinator::{repeat, preceded},
@@ -40,33 +42,197 @@ fn matches(winning: &Vec<i32>, numbers: &Vec<i32>) -> usize {
     numbers.iter().filter(|n| winning.contains(n)).count()
 }
 
-fn solve1(cards: &Vec<(Vec<i32>, Vec<i32>)>) -> i32 {
-    cards.iter().map(|(winning, numbers)| {
-        let c = matches(winning, numbers) as u32;
-        if c == 0 {
-            0
-        } else {
-            2i32.pow(c - 1)
+// Points for a card given its number of matches
+#[derive(Debug, Clone)]
+enum Scoring {
+    Doubling,      // 1 for the first match, doubled for each one after
+    Linear(u64),   // per match
+    Table(Vec<u64>), // indexed by the matches, the last entry counts for any more than that
+}
+
+impl Scoring {
+    // None if the points don't fit in a u64
+    fn score(&self, matches: usize) -> Option<u64> {
+        match self {
+            Scoring::Doubling if matches == 0 => Some(0),
+            Scoring::Doubling => u32::try_from(matches - 1).ok().and_then(|shift| 1u64.checked_shl(shift)),
+            Scoring::Linear(points) => points.checked_mul(matches as u64),
+            Scoring::Table(table) => Some(table.get(matches).or(table.last()).copied().unwrap_or(0)),
         }
-    }).sum()
+    }
 }
 
-// Sum the values from the end
-fn solve2(cards: &Vec<(Vec<i32>, Vec<i32>)>) -> i32 {
-    let mut values: Vec<i32> = vec![0i32; cards.len()];
-    for (i, (winning, numbers)) in cards.iter().rev().enumerate() {
-        let c = matches(winning, numbers);
-        values[i] = 1 + (1..=c).map(|n| values[i - n]).sum::<i32>();
+// Which cards a card wins copies of. It wins `per_match` following cards for each match, at most
+// `max` of them, and `multiplier` copies of each. Cards past the end of the deck are never won.
+#[derive(Debug, Clone)]
+struct CopyRule {
+    per_match: usize,
+    max: Option<usize>,
+    multiplier: u64,
+}
+
+impl CopyRule {
+    fn standard() -> Self {
+        CopyRule { per_match: 1, max: None, multiplier: 1 }
+    }
+
+    fn won(&self, card: usize, matches: usize, deck: usize) -> std::ops::Range<usize> {
+        let mut n = matches * self.per_match;
+        if let Some(max) = self.max {
+            n = n.min(max);
+        }
+        card + 1..(card + 1 + n).min(deck)
+    }
+}
+
+#[derive(Debug)]
+struct Copies {
+    count: Vec<u64>,                 // of each card, the original included
+    sources: Vec<Vec<(usize, u64)>>, // the cards each card's copies were won from, and how many
+}
+
+// The points or copies got too many for a u64 at a card, numbered from 1
+#[derive(Debug, PartialEq)]
+struct Overflow {
+    what: &'static str,
+    card: usize,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the {} overflow at card {}", self.what, self.card)
+    }
+}
+
+fn copies(cards: &[(Vec<i32>, Vec<i32>)], rule: &CopyRule) -> Result<Copies, Overflow> {
+    let mut count = vec![1u64; cards.len()];
+    let mut sources = vec![vec![]; cards.len()];
+    for (i, (winning, numbers)) in cards.iter().enumerate() {
+        let won = count[i].checked_mul(rule.multiplier).ok_or(Overflow { what: "copies", card: i + 1 })?;
+        for j in rule.won(i, matches(winning, numbers), cards.len()) {
+            count[j] = count[j].checked_add(won).ok_or(Overflow { what: "copies", card: j + 1 })?;
+            sources[j].push((i, won));
+        }
+    }
+    Ok(Copies { count, sources })
+}
+
+fn solve1(cards: &[(Vec<i32>, Vec<i32>)], scoring: &Scoring) -> Result<u64, Overflow> {
+    cards.iter().enumerate().try_fold(0u64, |total, (i, (winning, numbers))| {
+        scoring.score(matches(winning, numbers)).and_then(|points| total.checked_add(points))
+            .ok_or(Overflow { what: "points", card: i + 1 })
+    })
+}
+
+fn solve2(cards: &[(Vec<i32>, Vec<i32>)], rule: &CopyRule) -> Result<u64, Overflow> {
+    copies(cards, rule)?.count.iter().enumerate().try_fold(0u64, |total, (i, &n)| {
+        total.checked_add(n).ok_or(Overflow { what: "cards", card: i + 1 })
+    })
+}
+
+fn show(result: Result<u64, Overflow>) -> String {
+    result.map_or_else(|e| e.to_string(), |n| n.to_string())
+}
+
+// Cards are numbered from 1 like in the input
+fn print_cards(cards: &[(Vec<i32>, Vec<i32>)], scoring: &Scoring, rule: &CopyRule) {
+    let won = match copies(cards, rule) {
+        Ok(won) => won,
+        Err(e) => return eprintln!("{e}"),
     };
-    values.iter().sum()
+    for (i, (winning, numbers)) in cards.iter().enumerate() {
+        let c = matches(winning, numbers);
+        let sources = won.sources[i].iter().map(|(j, n)| format!("{n} from card {}", j + 1)).collect::<Vec<_>>();
+        println!("card {}: {} matches, {} points, {} copies won{}{}", i + 1, c,
+                 scoring.score(c).map_or("too many".to_string(), |points| points.to_string()), won.count[i] - 1,
+                 if sources.is_empty() { "" } else { ": " }, sources.join(", "));
+    }
+    println!("points: {}", show(solve1(cards, scoring)));
+    println!("cards: {}", show(solve2(cards, rule)));
+}
+
+struct Options {
+    scoring: Scoring,
+    rule: CopyRule,
+    cards: Option<String>,
+}
+
+fn parse_scoring(s: &str) -> Scoring {
+    match s.split_once(':') {
+        None if s == "doubling" => Scoring::Doubling,
+        Some(("linear", points)) => Scoring::Linear(points.parse().expect("Bad points")),
+        Some(("table", table)) => Scoring::Table(table.split(',').map(|n| n.parse().expect("Bad points")).collect()),
+        _ => panic!("Scoring is doubling, linear:N or table:N,N,..."),
+    }
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options { scoring: Scoring::Doubling, rule: CopyRule::standard(), cards: None };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{arg} needs a value"));
+        match arg.as_str() {
+            "--scoring" => opts.scoring = parse_scoring(&value()),
+            "--per-match" => opts.rule.per_match = value().parse().expect("Not a number"),
+            "--max" => opts.rule.max = Some(value().parse().expect("Not a number")),
+            "--multiplier" => opts.rule.multiplier = value().parse().expect("Not a number"),
+            "--cards" => opts.cards = Some(value()),
+            _ => panic!("Unknown argument {arg}"),
+        }
+    }
+    opts
 }
 
 fn main() {
+    let opts = parse_args();
+    if let Some(file) = &opts.cards {
+        return print_cards(&parse(file), &opts.scoring, &opts.rule);
+    }
+
     let example1 = parse("inputs/day4_ex1.txt");
     let input = parse("inputs/day4.txt");
 
-    println!("example1: {}", solve1(&example1));
-    println!("solution1: {}", solve1(&input));
-    println!("example2: {}", solve2(&example1));
-    println!("solution2: {}", solve2(&input));
-}
\ No newline at end of file
+    println!("example1: {}", show(solve1(&example1, &opts.scoring)));
+    println!("solution1: {}", show(part("sol1", || solve1(&input, &opts.scoring))));
+    println!("example2: {}", show(solve2(&example1, &opts.rule)));
+    println!("solution2: {}", show(part("sol2", || solve2(&input, &opts.rule))));
+}
+
+#[test]
+fn test_rules() {
+    // the example cards have 4, 2, 2, 1, 0 and 0 matches
+    let cards = parse("inputs/day4_ex1.txt");
+    assert_eq!(Ok(9), solve1(&cards, &Scoring::Linear(1)));
+    assert_eq!(Ok(17), solve1(&cards, &Scoring::Table(vec![0, 1, 3, 6, 10])));
+    assert_eq!(Ok(9), solve1(&cards, &Scoring::Table(vec![0, 1, 2, 4])));
+    assert_eq!(Ok(13), solve1(&cards, &Scoring::Table(vec![0, 1, 2, 4, 8])));
+    assert_eq!(Err(Overflow { what: "points", card: 2 }), solve1(&cards, &Scoring::Table(vec![0, 1, u64::MAX])));
+
+    assert_eq!(Some(1 << 63), Scoring::Doubling.score(64));
+    assert_eq!(None, Scoring::Doubling.score(65));
+    assert_eq!(None, Scoring::Linear(u64::MAX).score(2));
+
+    let won = copies(&cards, &CopyRule::standard()).unwrap();
+    assert_eq!(vec![1, 2, 4, 8, 14, 1], won.count);
+    assert_eq!(vec![(0, 1), (1, 2), (2, 4)], won.sources[3]);
+
+    assert_eq!(Ok(16), solve2(&cards, &CopyRule { max: Some(1), ..CopyRule::standard() }));
+    assert_eq!(Ok(116), solve2(&cards, &CopyRule { multiplier: 2, ..CopyRule::standard() }));
+    // card 2 gets 1 + 2^32 copies, and card 3 can't hold what card 2 wins
+    let huge = CopyRule { multiplier: 1 << 32, ..CopyRule::standard() };
+    assert_eq!(Err(Overflow { what: "copies", card: 2 }), copies(&cards, &huge).map(|_| ()));
+    assert_eq!("the copies overflow at card 2", solve2(&cards, &huge).unwrap_err().to_string());
+    let input = parse("inputs/day4.txt");
+    assert!(solve2(&input, &CopyRule { multiplier: 10, ..CopyRule::standard() }).is_err());
+    // card 1 would win 8 cards but the deck ends after card 6
+    let rule = CopyRule { per_match: 2, ..CopyRule::standard() };
+    assert_eq!(1..6, rule.won(0, 4, cards.len()));
+    assert_eq!((0, 1), copies(&cards, &rule).unwrap().sources[5][0]);
+}
+
+aoc2023::answers! {
+    ex1("inputs/day4_ex1.txt"): |f| solve1(&parse(f), &Scoring::Doubling).unwrap() => 13;
+    sol1("inputs/day4.txt"): |f| solve1(&parse(f), &Scoring::Doubling).unwrap() => 20855;
+    ex2("inputs/day4_ex1.txt"): |f| solve2(&parse(f), &CopyRule::standard()).unwrap() => 30;
+    sol2("inputs/day4.txt"): |f| solve2(&parse(f), &CopyRule::standard()).unwrap() => 5489600;
+}
@@ -1,4 +1,6 @@
-use std::{fs, cmp::{max, min}};
+use std::{collections::{HashMap, VecDeque}, env, fs, cmp::{max, min}};
+
+use aoc2023::stats::part;
 
 #[derive(Debug, Copy, Clone)]
 struct Range {
@@ -33,34 +35,197 @@ fn numbers(line: &str) -> Vec<i64> {
     line.split(' ').map(|n| n.parse::<i64>().unwrap()).collect()
 }
 
-fn parse(filename: &str) -> (Vec<i64>, Vec<Vec<RangeMap>>) {
-    let data = fs::read_to_string(filename).unwrap();
-    let parts: Vec<&str> = data.split("\n\n").collect();
+// A "from-to-to map:" block
+#[derive(Debug)]
+struct Block {
+    from: String,
+    to: String,
+    maps: Vec<RangeMap>,
+}
+
+#[derive(Debug)]
+struct Almanac {
+    seeds: Vec<i64>,
+    blocks: Vec<Block>,
+}
+
+fn parse(filename: &str) -> Almanac {
+    parse_almanac(&fs::read_to_string(filename).unwrap())
+}
+
+fn parse_almanac(data: &str) -> Almanac {
+    let parts: Vec<&str> = data.trim().split("\n\n").collect();
     let seeds: Vec<i64> = numbers(&parts[0][7..]);
 
-    let maps = parts[1..].iter()
+    let blocks = parts[1..].iter()
         .map(|part| {
-            part.splitn(2, ':').collect::<Vec<&str>>()[1].trim().lines()
+            let (header, body) = part.split_once(':').unwrap();
+            let name = header.strip_suffix(" map").unwrap();
+            let (from, to) = name.split_once("-to-").unwrap();
+            let maps = body.trim().lines()
                 .map(|line| {
                     let nums = numbers(line);
                     RangeMap::new(nums[0], nums[1], nums[2])
                 })
-                .collect::<Vec<RangeMap>>()
+                .collect::<Vec<RangeMap>>();
+            Block { from: from.to_string(), to: to.to_string(), maps }
         })
         .collect();
 
-    (seeds, maps)
+    Almanac { seeds, blocks }
+}
+
+impl Almanac {
+    // The blocks leading from one category to another, whatever order they are given in
+    fn chain(&self, from: &str, to: &str) -> Option<Vec<&Block>> {
+        let mut prev: HashMap<&str, &Block> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut chain = vec![];
+                let mut at = to;
+                while at != from {
+                    let block = prev[at];
+                    chain.push(block);
+                    at = &block.from;
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            for block in self.blocks.iter().filter(|b| b.from == category) {
+                if block.to != from && !prev.contains_key(block.to.as_str()) {
+                    prev.insert(&block.to, block);
+                    queue.push_back(&block.to);
+                }
+            }
+        }
+        None
+    }
+
+    fn chain_or_panic(&self, from: &str, to: &str) -> Vec<&Block> {
+        self.chain(from, to).unwrap_or_else(|| panic!("No maps lead from {from} to {to}"))
+    }
+
+    fn map(&self, from: &str, to: &str, n: i64) -> i64 {
+        self.chain_or_panic(from, to).iter().fold(n, |n, block| map(n, &block.maps))
+    }
+
+    fn map_ranges(&self, from: &str, to: &str, ranges: Vec<Range>) -> Vec<Range> {
+        self.chain_or_panic(from, to).iter().fold(ranges, |ranges, block| map_ranges(ranges, &block.maps))
+    }
+
+    // All `from` values that end up in the given `to` ranges
+    fn preimage(&self, from: &str, to: &str, ranges: Vec<Range>) -> Vec<Range> {
+        self.chain_or_panic(from, to).iter().rev().fold(ranges, |ranges, block| preimage_ranges(ranges, &block.maps))
+    }
+
+    // The value in each category on the way, starting with `from`
+    fn trace<'a>(&'a self, from: &'a str, to: &str, n: i64) -> Vec<(&'a str, i64)> {
+        let mut trace = vec![(from, n)];
+        let mut n = n;
+        for block in self.chain_or_panic(from, to) {
+            n = map(n, &block.maps);
+            trace.push((&block.to, n));
+        }
+        trace
+    }
+}
+
+// A whole chain of maps as one function. Piece i sends [starts[i], starts[i + 1]) to itself plus
+// shifts[i], the last piece going on forever. The first piece starts at i64::MIN, and the pieces
+// at both ends have no shift since every map covers a bounded range.
+#[derive(Debug, PartialEq)]
+struct Piecewise {
+    starts: Vec<i64>,
+    shifts: Vec<i64>,
+}
+
+impl Piecewise {
+    fn identity() -> Self {
+        Piecewise { starts: vec![i64::MIN], shifts: vec![0] }
+    }
+
+    // The same function as `map` with these maps, where the first map covering a value wins
+    fn from_maps(maps: &[RangeMap]) -> Self {
+        let mut bounds = maps.iter().flat_map(|m| [m.start, m.end]).collect::<Vec<_>>();
+        bounds.sort();
+        bounds.dedup();
+        let mut pieces = Piecewise::identity();
+        for &start in bounds.iter() {
+            let shift = maps.iter().find(|m| m.start <= start && start < m.end).map_or(0, |m| m.shift);
+            pieces.push(start, shift);
+        }
+        pieces
+    }
+
+    fn push(&mut self, start: i64, shift: i64) {
+        if *self.starts.last().unwrap() == start {
+            self.shifts.pop();
+            self.starts.pop();
+        }
+        if self.shifts.last() != Some(&shift) {
+            self.starts.push(start);
+            self.shifts.push(shift);
+        }
+    }
+
+    fn end(&self, i: usize) -> i64 {
+        self.starts.get(i + 1).copied().unwrap_or(i64::MAX)
+    }
+
+    fn piece(&self, n: i64) -> usize {
+        self.starts.partition_point(|&start| start <= n) - 1
+    }
+
+    fn apply(&self, n: i64) -> i64 {
+        n + self.shifts[self.piece(n)]
+    }
+
+    // This function followed by `next`
+    fn then(&self, next: &Piecewise) -> Piecewise {
+        let mut composed = Piecewise::identity();
+        for (i, (&start, &shift)) in self.starts.iter().zip(self.shifts.iter()).enumerate() {
+            let (image_start, image_end) = (start + shift, self.end(i) + shift);
+            let mut j = next.piece(image_start);
+            while j < next.starts.len() && next.starts[j] < image_end {
+                let from = max(image_start, next.starts[j]);
+                composed.push(from - shift, shift + next.shifts[j]);
+                j += 1;
+            }
+        }
+        composed
+    }
+
+    // The smallest value the function takes on the ranges
+    fn min_over(&self, ranges: &[Range]) -> Option<i64> {
+        ranges.iter().filter(|r| r.start < r.end).flat_map(|r| {
+            (self.piece(r.start)..self.piece(r.end - 1) + 1).map(move |i| max(r.start, self.starts[i]) + self.shifts[i])
+        }).min()
+    }
+
+    // The bounded pieces, as (start, end, shift)
+    fn breakpoints(&self) -> impl Iterator<Item = (i64, i64, i64)> + '_ {
+        (1..self.starts.len() - 1).map(|i| (self.starts[i], self.end(i), self.shifts[i]))
+    }
 }
 
-fn solve1((seeds, category_maps): &(Vec<i64>, Vec<Vec<RangeMap>>)) -> i64 {
-    let mut nums = seeds.to_owned();
-    for maps in category_maps {
-        nums = nums.iter().map(|n| map(*n, maps)).collect();
+impl Almanac {
+    fn compose(&self, from: &str, to: &str) -> Piecewise {
+        self.chain_or_panic(from, to).iter().fold(Piecewise::identity(), |f, block| f.then(&Piecewise::from_maps(&block.maps)))
     }
-    *nums.iter().min().unwrap()
 }
 
-fn map(n: i64, maps: &Vec<RangeMap>) -> i64 {
+fn solve1(almanac: &Almanac) -> i64 {
+    let f = almanac.compose("seed", "location");
+    almanac.seeds.iter().map(|&n| f.apply(n)).min().unwrap()
+}
+
+// Mapping the seeds one category at a time
+fn solve1_stepwise(almanac: &Almanac) -> i64 {
+    almanac.seeds.iter().map(|&n| almanac.map("seed", "location", n)).min().unwrap()
+}
+
+fn map(n: i64, maps: &[RangeMap]) -> i64 {
     for map in maps {
         if n >= map.start && n < map.end {
             return n + map.shift
@@ -69,21 +234,24 @@ fn map(n: i64, maps: &Vec<RangeMap>) -> i64 {
     n
 }
 
-fn solve2((seeds, category_maps): &(Vec<i64>, Vec<Vec<RangeMap>>)) -> i64 {
-    let mut ranges = seeds.chunks_exact(2).map(|range|{
+fn seed_ranges(almanac: &Almanac) -> Vec<Range> {
+    almanac.seeds.chunks_exact(2).map(|range|{
         let start = range[0];
         let len = range[1];
         Range::new(start, start + len)
-    }).collect();
+    }).collect()
+}
 
-    for maps in category_maps.iter() {
-        ranges = map_ranges(ranges, maps);
-    }
+fn solve2(almanac: &Almanac) -> i64 {
+    almanac.compose("seed", "location").min_over(&seed_ranges(almanac)).unwrap()
+}
 
+fn solve2_stepwise(almanac: &Almanac) -> i64 {
+    let ranges = almanac.map_ranges("seed", "location", seed_ranges(almanac));
     ranges.iter().map(|r| r.start).min().unwrap()
 }
 
-fn map_ranges(ranges: Vec<Range>, maps: &Vec<RangeMap>) -> Vec<Range> {
+fn map_ranges(ranges: Vec<Range>, maps: &[RangeMap]) -> Vec<Range> {
     let mut unmapped = ranges;
     let mut mapped = vec![];
 
@@ -110,14 +278,90 @@ fn map_ranges(ranges: Vec<Range>, maps: &Vec<RangeMap>) -> Vec<Range> {
     mapped
 }
 
+// The ranges that `maps` sends into `ranges`. Values outside every map are mapped to themselves, so
+// they are their own preimage unless some map covers them.
+fn preimage_ranges(ranges: Vec<Range>, maps: &[RangeMap]) -> Vec<Range> {
+    let mut preimage = vec![];
+    for range in ranges.iter() {
+        for map in maps {
+            let start = max(range.start, map.start + map.shift);
+            let end = min(range.end, map.end + map.shift);
+            if start < end {
+                preimage.push(Range::new(start - map.shift, end - map.shift));
+            }
+        }
+        // the parts of the range not covered by any map
+        let mut uncovered = vec![*range];
+        for map in maps {
+            uncovered = uncovered.into_iter().flat_map(|r| {
+                if !r.has_overlap(map) {
+                    return vec![r];
+                }
+                [Range::new(r.start, map.start), Range::new(map.end, r.end)].into_iter()
+                    .filter(|r| r.start < r.end)
+                    .collect()
+            }).collect();
+        }
+        preimage.extend(uncovered);
+    }
+    preimage
+}
+
+struct Options {
+    input: String,
+    trace: Option<i64>,
+    reverse: Option<i64>,
+    breakpoints: bool,
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options { input: "inputs/day5.txt".to_string(), trace: None, reverse: None, breakpoints: false };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{arg} needs a value"));
+        match arg.as_str() {
+            "--input" => opts.input = value(),
+            "--trace" => opts.trace = Some(value().parse().expect("Not a seed")),
+            "--reverse" => opts.reverse = Some(value().parse().expect("Not a location")),
+            "--breakpoints" => opts.breakpoints = true,
+            _ => panic!("Unknown argument {arg}"),
+        }
+    }
+    opts
+}
+
 fn main() {
+    let opts = parse_args();
+    if opts.trace.is_some() || opts.reverse.is_some() || opts.breakpoints {
+        let almanac = parse(&opts.input);
+        if let Some(seed) = opts.trace {
+            let trace = almanac.trace("seed", "location", seed);
+            println!("{}", trace.iter().map(|(category, n)| format!("{category} {n}")).collect::<Vec<_>>().join(", "));
+        }
+        if let Some(location) = opts.reverse {
+            let mut seeds = almanac.preimage("seed", "location", vec![Range::new(location, location + 1)]);
+            seeds.sort_by_key(|r| r.start);
+            for r in seeds {
+                println!("seeds {}..{}", r.start, r.end);
+            }
+        }
+        if opts.breakpoints {
+            for (start, end, shift) in almanac.compose("seed", "location").breakpoints() {
+                println!("{start}..{end} {shift:+}");
+            }
+        }
+        return;
+    }
+
     let ex = parse("inputs/day5_ex1.txt");
     let input = parse("inputs/day5.txt");
 
     println!("ex1: {}", solve1(&ex));
-    println!("solution1: {}", solve1(&input));
+    println!("ex1 stepwise: {}", solve1_stepwise(&ex));
+    println!("solution1: {}", part("sol1", || solve1(&input)));
     println!("ex2: {}", solve2(&ex));
-    println!("solution2: {}", solve2(&input));
+    println!("ex2 stepwise: {}", solve2_stepwise(&ex));
+    println!("solution2: {}", part("sol2", || solve2(&input)));
 }
 
 #[test]
@@ -130,4 +374,69 @@ fn test_map() {
     assert_eq!(52, map(50, &ex1));
     assert_eq!(99, map(97, &ex1));
     assert_eq!(50, map(98, &ex1));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_almanac() {
+    let almanac = parse("inputs/day5_ex1.txt");
+    assert_eq!(vec![("seed", 79), ("soil", 81), ("fertilizer", 81), ("water", 81), ("light", 74),
+                    ("temperature", 78), ("humidity", 78), ("location", 82)],
+               almanac.trace("seed", "location", 79));
+    assert_eq!(74, almanac.map("water", "light", 81));
+    assert!(almanac.chain("location", "seed").is_none());
+
+    // the blocks can come in any order
+    let data = fs::read_to_string("inputs/day5_ex1.txt").unwrap();
+    let mut parts: Vec<&str> = data.trim().split("\n\n").collect();
+    parts[1..].reverse();
+    let shuffled = parse_almanac(&parts.join("\n\n"));
+    assert_eq!("humidity", shuffled.blocks[0].from);
+    assert_eq!(35, solve1(&shuffled));
+    assert_eq!(46, solve2(&shuffled));
+}
+
+#[test]
+fn test_preimage() {
+    let almanac = parse("inputs/day5_ex1.txt");
+    let seeds = almanac.preimage("seed", "location", vec![Range::new(46, 47)]);
+    assert!(seeds.iter().any(|r| r.start <= 82 && 82 < r.end));
+    for r in seeds.iter() {
+        for n in r.start..r.end {
+            assert_eq!(46, almanac.map("seed", "location", n));
+        }
+    }
+
+    // the example maps only shuffle values around, so 100 locations come from 100 seeds
+    let seeds = almanac.preimage("seed", "location", vec![Range::new(0, 100)]);
+    assert_eq!(100, seeds.iter().map(|r| r.end - r.start).sum::<i64>());
+}
+
+#[test]
+fn test_piecewise() {
+    let maps = vec![
+        RangeMap::new(50, 98, 2),
+        RangeMap::new(52, 50, 48),
+    ];
+    let f = Piecewise::from_maps(&maps);
+    assert_eq!(vec![(50, 98, 2), (98, 100, -48)], f.breakpoints().collect::<Vec<_>>());
+    for n in -5..110 {
+        assert_eq!(map(n, &maps), f.apply(n));
+    }
+    assert_eq!(f, f.then(&Piecewise::identity()));
+
+    let almanac = parse("inputs/day5_ex1.txt");
+    let f = almanac.compose("seed", "location");
+    for n in -5..110 {
+        assert_eq!(almanac.map("seed", "location", n), f.apply(n));
+    }
+    assert_eq!(Some(46), f.min_over(&[Range::new(55, 68), Range::new(79, 93)]));
+    assert_eq!(Some(82), f.min_over(&[Range::new(79, 80)]));
+    assert_eq!(None, f.min_over(&[]));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day5_ex1.txt"): |f| solve1(&parse(f)) => 35;
+    sol1("inputs/day5.txt"): |f| solve1(&parse(f)) => 662197086;
+    ex2("inputs/day5_ex1.txt"): |f| solve2(&parse(f)) => 46;
+    sol2("inputs/day5.txt"): |f| solve2(&parse(f)) => 52510809;
+}
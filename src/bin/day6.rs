@@ -1,29 +1,375 @@
+use std::{env, fs};
+
+use aoc2023::stats::part;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Race {
+    time: u64,
+    record: u64,
+}
+
+// How the columns of the sheet are read: one race per column, or all the digits of a row as
+// a single number because of bad kerning
+#[derive(Debug, Clone, Copy)]
+enum Reading {
+    Spaced,
+    Kerned,
+}
+
+fn parse(filename: &str, reading: Reading) -> Vec<Race> {
+    parse_sheet(&fs::read_to_string(filename).unwrap(), reading)
+}
+
+fn parse_sheet(sheet: &str, reading: Reading) -> Vec<Race> {
+    let row = |label: &str| -> Vec<u64> {
+        let line = sheet.lines().find_map(|line| line.strip_prefix(label))
+            .unwrap_or_else(|| panic!("No {label} row"));
+        match reading {
+            Reading::Spaced => line.split_whitespace().map(|n| n.parse().unwrap()).collect(),
+            Reading::Kerned => vec![line.replace(' ', "").parse().unwrap()],
+        }
+    };
+    let times = row("Time:");
+    let records = row("Distance:");
+    assert_eq!(times.len(), records.len(), "Every race needs a time and a distance");
+    times.into_iter().zip(records).map(|(time, record)| Race { time, record }).collect()
+}
+
+impl Race {
+    fn wins(&self, hold: u64) -> bool {
+        hold <= self.time && hold as u128 * (self.time - hold) as u128 > self.record as u128
+    }
+
+    // The shortest and longest hold that beat the record. The distance hold * (time - hold) is
+    // symmetric around time / 2, so the roots are (time ± sqrt(time² - 4 * record)) / 2. The
+    // integer square root is exact, but the halving rounds so the bound is nudged into place.
+    fn winning(&self) -> Option<(u64, u64)> {
+        // the best hold is half the time
+        if !self.wins(self.time / 2) {
+            return None;
+        }
+        let (t, d) = (self.time as u128, self.record as u128);
+        let root = (t * t - 4 * d).isqrt();
+        let mut lo = ((t - root) / 2) as u64;
+        while !self.wins(lo) {
+            lo += 1;
+        }
+        while lo > 0 && self.wins(lo - 1) {
+            lo -= 1;
+        }
+        Some((lo, self.time - lo))
+    }
+
+    fn ways(&self) -> u64 {
+        self.winning().map_or(0, |(lo, hi)| hi - lo + 1)
+    }
+}
+
+// How the boat moves. Each millisecond the button is held adds `accel` to the speed, up to the
+// top speed. The race is split into phases, each multiplying the distance covered during it by
+// its factor, e.g. for a current. The race goes on with factor 1 after the last phase.
+#[derive(Debug, Clone)]
+struct Physics {
+    accel: u64,
+    top_speed: Option<u64>,
+    phases: Vec<Phase>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Phase {
+    duration: u64,
+    factor: u64,
+}
+
+impl Physics {
+    fn standard() -> Self {
+        Physics { accel: 1, top_speed: None, phases: vec![] }
+    }
+
+    // The phases as [start, end) intervals covering exactly the race
+    fn phases(&self, time: u64) -> Vec<(u64, u64, u64)> {
+        let mut phases = vec![];
+        let mut start = 0;
+        for phase in self.phases.iter() {
+            let end = (start + phase.duration).min(time);
+            if end > start {
+                phases.push((start, end, phase.factor));
+            }
+            start = end;
+        }
+        if start < time {
+            phases.push((start, time, 1));
+        }
+        phases
+    }
+
+    fn speed(&self, hold: u64) -> u128 {
+        let speed = self.accel as u128 * hold as u128;
+        self.top_speed.map_or(speed, |top| speed.min(top as u128))
+    }
+
+    fn distance(&self, time: u64, hold: u64) -> u128 {
+        let moving: u128 = self.phases(time).iter()
+            .map(|&(start, end, factor)| factor as u128 * (end - start.max(hold).min(end)) as u128)
+            .sum();
+        self.speed(hold) * moving
+    }
+
+    // The distance as a polynomial in the hold time on each stretch of holds where the speed is
+    // either still growing or capped and the boat sets off in the same phase
+    fn pieces(&self, time: u64) -> Vec<Piece> {
+        let phases = self.phases(time);
+        // the first hold reaching the top speed
+        let capped_at = self.top_speed.filter(|_| self.accel > 0).map(|top| top.div_ceil(self.accel));
+        let mut bounds = phases.iter().map(|&(start, _, _)| start).chain(capped_at).chain([time + 1])
+            .filter(|&b| b <= time + 1)
+            .collect::<Vec<_>>();
+        bounds.sort();
+        bounds.dedup();
+
+        bounds.windows(2).map(|w| {
+            let (lo, hi) = (w[0], w[1] - 1);
+            // speed = v1 * hold + v0
+            let (v1, v0) = match capped_at {
+                Some(c) if lo >= c => (0, self.top_speed.unwrap() as i128),
+                _ => (self.accel as i128, 0),
+            };
+            // distance moving at speed 1 = m1 * hold + m0
+            let (mut m1, mut m0) = (0, 0);
+            for &(start, end, factor) in phases.iter() {
+                if start > lo {
+                    m0 += factor as i128 * (end - start) as i128;
+                } else if lo < end {
+                    m1 -= factor as i128;
+                    m0 += factor as i128 * end as i128;
+                }
+            }
+            Piece { lo, hi, poly: Poly([v0 * m0, v1 * m0 + v0 * m1, v1 * m1]) }
+        }).collect()
+    }
+
+    // The holds beating the record, as disjoint inclusive intervals in order
+    fn winning(&self, race: &Race) -> Vec<(u64, u64)> {
+        let mut winning: Vec<(u64, u64)> = vec![];
+        for piece in self.pieces(race.time) {
+            for (lo, hi) in piece.above(race.record as i128) {
+                match winning.last_mut() {
+                    Some(last) if last.1 + 1 >= lo => last.1 = last.1.max(hi),
+                    _ => winning.push((lo, hi)),
+                }
+            }
+        }
+        winning
+    }
+}
+
+// c[0] + c[1] * x + c[2] * x²
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Poly([i128; 3]);
+
+impl Poly {
+    fn at(&self, x: u64) -> i128 {
+        let x = x as i128;
+        self.0[0] + self.0[1] * x + self.0[2] * x * x
+    }
+}
+
+#[derive(Debug)]
+struct Piece {
+    lo: u64,
+    hi: u64, // inclusive
+    poly: Poly,
+}
+
+// The first x in lo..=hi where `pred` holds, given that it holds for all x after that
+fn first(lo: u64, hi: u64, pred: impl Fn(u64) -> bool) -> Option<u64> {
+    let (mut lo, mut end) = (lo, hi + 1);
+    while lo < end {
+        let mid = lo + (end - lo) / 2;
+        if pred(mid) {
+            end = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    (lo <= hi).then_some(lo)
+}
+
+impl Piece {
+    // Where the polynomial is above `record`. A polynomial of degree at most 2 is monotone on
+    // both sides of the point where its differences change sign, so each side is searched for
+    // where it crosses the record.
+    fn above(&self, record: i128) -> Vec<(u64, u64)> {
+        let p = self.poly;
+        let diff = |x: u64| p.at(x + 1) - p.at(x);
+        let turn = if self.lo == self.hi {
+            self.lo
+        } else if p.0[2] < 0 {
+            first(self.lo, self.hi - 1, |x| diff(x) <= 0).unwrap_or(self.hi)
+        } else {
+            first(self.lo, self.hi - 1, |x| diff(x) >= 0).unwrap_or(self.hi)
+        };
+
+        let wins = |x: u64| p.at(x) > record;
+        let mut above = vec![];
+        // from lo to the turn, then from the turn to hi
+        for (lo, hi) in [(self.lo, turn), (turn, self.hi)] {
+            let interval = if p.at(hi) >= p.at(lo) {
+                first(lo, hi, wins).map(|x| (x, hi))
+            } else {
+                wins(lo).then(|| (lo, first(lo, hi, |x| !wins(x)).map_or(hi, |x| x - 1)))
+            };
+            if let Some((lo, hi)) = interval {
+                match above.last_mut() {
+                    Some((_, last)) if *last + 1 >= lo => *last = hi.max(*last),
+                    _ => above.push((lo, hi)),
+                }
+            }
+        }
+        above
+    }
+}
+
+fn parse_phases(s: &str) -> Vec<Phase> {
+    s.split(',').map(|phase| {
+        let (duration, factor) = phase.split_once(':').expect("Phases are given as DURATION:FACTOR");
+        Phase { duration: duration.parse().expect("Bad duration"), factor: factor.parse().expect("Bad factor") }
+    }).collect()
+}
+
+// Some(physics) if any of the physics was changed on the command line
+fn parse_args() -> Option<Physics> {
+    let mut physics = Physics::standard();
+    let mut changed = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{arg} needs a value"));
+        match arg.as_str() {
+            "--accel" => physics.accel = value().parse().expect("Bad acceleration"),
+            "--top-speed" => physics.top_speed = Some(value().parse().expect("Bad speed")),
+            "--phases" => physics.phases = parse_phases(&value()),
+            _ => panic!("Unknown argument {arg}"),
+        }
+        changed = true;
+    }
+    changed.then_some(physics)
+}
+
 // Bruteforce
-fn solve1((ts, ds): (Vec<i64>, Vec<i64>)) -> usize {
-    ts.iter().zip(ds).map(|(t, d)| {
-        let n = (1..*t).filter(|x| x * (t-x) > d).count();
-        n
+fn solve1(races: &[Race]) -> usize {
+    races.iter().map(|race| {
+        (1..race.time).filter(|&x| race.wins(x)).count()
     }).product()
 }
 
-// Solve using pq-formula
-fn solve2((t, d): (f64, f64)) -> f64{
-    let a = t / 2.0;
-    let b = ((t / 2.0f64).powf(2.0) - d).sqrt();
-    let x0 = (a - b).floor()+1.0;
-    let x1 = (a + b).ceil();
-    x1 - x0
+fn solve2(races: &[Race]) -> u64 {
+    races.iter().map(|race| race.ways()).product()
 }
 
-
 fn main() {
-    let ex1 = (vec![7, 15, 30], vec![9, 40, 200]);
-    let ex2: (f64, f64) = (71530.0, 940200.0);
-    let inp1 = (vec![47, 70, 75, 66], vec![282, 1079, 1147, 1062]);
-    let inp2: (f64, f64) = (47707566.0, 282107911471062.0);
-
-    println!("ex1: {}", solve1(ex1));
-    println!("sol1: {}", solve1(inp1));
-    println!("ex2: {}", solve2(ex2));
-    println!("sol2: {}", solve2(inp2));
-}
\ No newline at end of file
+    if let Some(physics) = parse_args() {
+        for race in parse("inputs/day6.txt", Reading::Spaced) {
+            let holds = physics.winning(&race).iter()
+                .map(|&(lo, hi)| format!("{lo}..={hi} ({lo} goes {})", physics.distance(race.time, lo)))
+                .collect::<Vec<_>>();
+            println!("race {} ms, record {}: {}", race.time, race.record, holds.join(", "));
+        }
+        return;
+    }
+
+    let ex1 = parse("inputs/day6_ex1.txt", Reading::Spaced);
+    let ex2 = parse("inputs/day6_ex1.txt", Reading::Kerned);
+    let inp1 = parse("inputs/day6.txt", Reading::Spaced);
+    let inp2 = parse("inputs/day6.txt", Reading::Kerned);
+
+    println!("ex1: {}", solve1(&ex1));
+    println!("sol1: {}", part("sol1", || solve1(&inp1)));
+    println!("ex2: {}", solve2(&ex2));
+    println!("sol2: {}", part("sol2", || solve2(&inp2)));
+    let (lo, hi) = inp2[0].winning().unwrap();
+    println!("sol2 holds: {lo}..={hi}");
+}
+
+#[test]
+fn test_winning() {
+    let races = parse("inputs/day6_ex1.txt", Reading::Spaced);
+    assert_eq!(vec![Race { time: 7, record: 9 }, Race { time: 15, record: 40 }, Race { time: 30, record: 200 }], races);
+    assert_eq!(vec![Race { time: 71530, record: 940200 }], parse("inputs/day6_ex1.txt", Reading::Kerned));
+
+    assert_eq!(Some((2, 5)), races[0].winning());
+    assert_eq!(Some((4, 11)), races[1].winning());
+    // 10 and 20 only tie the record, the roots are exact
+    assert_eq!(Some((11, 19)), races[2].winning());
+    assert_eq!(None, Race { time: 10, record: 25 }.winning());
+    assert_eq!(Some((5, 5)), Race { time: 10, record: 24 }.winning());
+    assert_eq!(None, Race { time: 1, record: 0 }.winning());
+
+    for race in races.iter() {
+        assert_eq!(solve1(&[*race]) as u64, race.ways());
+    }
+    let big = Race { time: u64::MAX / 2, record: 1 << 60 };
+    let (lo, hi) = big.winning().unwrap();
+    assert!(big.wins(lo) && !big.wins(lo - 1) && big.wins(hi) && !big.wins(hi + 1));
+}
+
+#[test]
+fn test_physics() {
+    let brute = |physics: &Physics, race: &Race| {
+        let mut winning: Vec<(u64, u64)> = vec![];
+        for hold in (0..=race.time).filter(|&h| physics.distance(race.time, h) > race.record as u128) {
+            match winning.last_mut() {
+                Some(last) if last.1 + 1 == hold => last.1 = hold,
+                _ => winning.push((hold, hold)),
+            }
+        }
+        winning
+    };
+
+    let standard = Physics::standard();
+    for race in parse("inputs/day6.txt", Reading::Spaced) {
+        assert_eq!(vec![race.winning().unwrap()], standard.winning(&race));
+    }
+    let race = parse("inputs/day6.txt", Reading::Kerned)[0];
+    assert_eq!(vec![race.winning().unwrap()], standard.winning(&race));
+
+    // fast through the first 10 ms, so an early or a late start wins but not one around 10
+    let physics = Physics { phases: vec![Phase { duration: 10, factor: 50 }], ..Physics::standard() };
+    let race = Race { time: 110, record: 1500 };
+    assert_eq!(vec![(4, 8), (16, 94)], physics.winning(&race));
+    assert_eq!(brute(&physics, &race), physics.winning(&race));
+
+    let physics = Physics {
+        accel: 3,
+        top_speed: Some(40),
+        phases: vec![Phase { duration: 7, factor: 1 }, Phase { duration: 5, factor: 0 }, Phase { duration: 9, factor: 4 }],
+    };
+    for record in [0, 50, 200, 500, 900, 1400] {
+        let race = Race { time: 30, record };
+        for piece in physics.pieces(race.time) {
+            for hold in piece.lo..=piece.hi {
+                assert_eq!(physics.distance(race.time, hold) as i128, piece.poly.at(hold));
+            }
+        }
+        assert_eq!(brute(&physics, &race), physics.winning(&race));
+    }
+
+    for accel in 0..4 {
+        for top_speed in [None, Some(0), Some(5), Some(12)] {
+            for phases in [vec![], vec![(3, 2)], vec![(4, 0), (6, 3)], vec![(2, 5), (5, 1), (1, 9)]] {
+                let phases = phases.into_iter().map(|(duration, factor)| Phase { duration, factor }).collect();
+                let physics = Physics { accel, top_speed, phases };
+                for record in [0, 10, 40, 100] {
+                    let race = Race { time: 20, record };
+                    assert_eq!(brute(&physics, &race), physics.winning(&race), "{physics:?} {race:?}");
+                }
+            }
+        }
+    }
+}
+
+aoc2023::answers! {
+    ex1("inputs/day6_ex1.txt"): |f| solve1(&parse(f, Reading::Spaced)) => 288;
+    sol1("inputs/day6.txt"): |f| solve1(&parse(f, Reading::Spaced)) => 281600;
+    ex2("inputs/day6_ex1.txt"): |f| solve2(&parse(f, Reading::Kerned)) => 71503;
+    sol2("inputs/day6.txt"): |f| solve2(&parse(f, Reading::Kerned)) => 33875953;
+}
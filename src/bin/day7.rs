@@ -1,82 +1,229 @@
-use std::{fs, cmp::Ordering};
+use std::{env, fmt, fs};
 
-fn parse(filename: &str) -> Vec<(Vec<u32>, usize)> {
+use aoc2023::stats::part;
+
+const CARDS: &str = "23456789TJQKA";
+
+fn parse(filename: &str) -> Vec<(String, usize)> {
     fs::read_to_string(filename).unwrap().lines().filter(|line| !line.is_empty())
     .map(|line| {
         let mut parts = line.split(' ');
-        let cards = parts.next().unwrap().chars().map(|c| card_value(c)).collect();
+        let cards = parts.next().unwrap().to_string();
         let bid = parts.next().unwrap().parse::<usize>().unwrap();
         (cards, bid)
     })
     .collect()
 }
 
-fn card_value(c: char) -> u32 {
-    match c {
-        'A' => 14,
-        'K' => 13,
-        'Q' => 12,
-        'J' => 11,
-        'T' => 10,
-        d => d.to_digit(10).unwrap()
+// Weakest first, so the derived Ord ranks them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+impl HandType {
+    // From the counts of each kind of card, largest first
+    fn from_counts(counts: &[usize]) -> Self {
+        match counts {
+            [5, ..] => HandType::FiveOfAKind,
+            [4, ..] => HandType::FourOfAKind,
+            [3, 2, ..] => HandType::FullHouse,
+            [3, ..] => HandType::ThreeOfAKind,
+            [2, 2, ..] => HandType::TwoPair,
+            [2, ..] => HandType::OnePair,
+            _ => HandType::HighCard,
+        }
     }
 }
 
-fn solve1(hands: &Vec<(Vec<u32>, usize)>) -> usize {
-    let mut hands = hands.to_owned();
-    hands.sort_by(|(cards1, _), (cards2, _)| compare(cards1, cards2));
-    hands.iter().enumerate()
-      .map(|(i, (_, bid))| (i+1) * bid)
-      .sum()
+impl fmt::Display for HandType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            HandType::HighCard => "high card",
+            HandType::OnePair => "one pair",
+            HandType::TwoPair => "two pair",
+            HandType::ThreeOfAKind => "three of a kind",
+            HandType::FullHouse => "full house",
+            HandType::FourOfAKind => "four of a kind",
+            HandType::FiveOfAKind => "five of a kind",
+        };
+        write!(f, "{name}")
+    }
 }
 
-fn solve2(hands: &Vec<(Vec<u32>, usize)>) -> usize {
-    // For J, replace 11 with 1
-    let mut hands = hands.clone();
-    hands.iter_mut().for_each(|(cards, _)| {
-        cards.iter_mut().for_each(|v| if *v == 11 { *v = 1 })}
-    );
-    solve1(&hands)
+// How hands of the same type are ordered
+#[derive(Debug, Clone, Copy)]
+enum TieBreak {
+    InOrder, // the first card that differs, as dealt
+    Grouped, // the largest group first like in poker, e.g. the three of a full house before the pair
 }
 
-fn compare(h1: &Vec<u32>, h2: &Vec<u32>) -> Ordering {
-    let hc1 = hand_counts(h1);
-    let hc2 = hand_counts(h2);
-    let diff = hc1.into_iter().zip(hc2).find(|(x, y)| x != y);
-    if let Some((a, b)) = diff {
-        // return the first that has more cards of the same value
-        return a.cmp(&b);
-    }
-    let (a, b) = h1.iter().zip(h2).find(|(x, y)| x != y).unwrap();
-    a.cmp(b)
+// Wild cards count as whatever makes the best hand type, and are weaker than any other card
+#[derive(Debug, Clone)]
+struct Rules {
+    wild: Vec<char>,
+    tie_break: TieBreak,
 }
 
-fn hand_counts(h: &Vec<u32>) -> Vec<usize> {
-    let jacks = h.iter().filter(|c| **c == 1).count();
-    if jacks == 5 {
-        return vec![5];
+impl Rules {
+    fn standard() -> Self {
+        Rules { wild: vec![], tie_break: TieBreak::InOrder }
     }
 
-    let mut seen = vec![];
-    let mut counts = vec![];
-    while let Some(v) = h.iter().find(|c| **c != 1 && !seen.contains(c)) {
-        let cnt = h.iter().filter(|c| *c == v).count();
-        seen.push(v);
-        counts.push(cnt);
+    fn jokers() -> Self {
+        Rules { wild: vec!['J'], tie_break: TieBreak::InOrder }
     }
 
-    counts.sort();
-    counts.reverse();
-    counts[0] += jacks;
-    counts
+    fn strength(&self, card: char) -> u8 {
+        if self.wild.contains(&card) {
+            return 0;
+        }
+        CARDS.find(card).unwrap_or_else(|| panic!("Unknown card {card}")) as u8 + 1
+    }
+}
+
+// The fields are compared in order
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Hand {
+    kind: HandType,
+    key: Vec<u8>,
+    cards: String,
+}
+
+impl Hand {
+    fn new(cards: &str, rules: &Rules) -> Self {
+        let strengths = cards.chars().map(|c| rules.strength(c)).collect::<Vec<_>>();
+        let wild = strengths.iter().filter(|&&s| s == 0).count();
+
+        // (count, strength) of each kind of card that isn't wild, largest group first
+        let mut groups: Vec<(usize, u8)> = vec![];
+        for &s in strengths.iter().filter(|&&s| s != 0) {
+            match groups.iter_mut().find(|(_, g)| *g == s) {
+                Some((count, _)) => *count += 1,
+                None => groups.push((1, s)),
+            }
+        }
+        groups.sort_by(|a, b| b.cmp(a));
+
+        let mut counts = groups.iter().map(|&(count, _)| count).collect::<Vec<_>>();
+        match counts.first_mut() {
+            Some(most) => *most += wild,
+            None => counts.push(wild),
+        }
+
+        let key = match rules.tie_break {
+            TieBreak::InOrder => strengths,
+            TieBreak::Grouped => groups.iter()
+                .flat_map(|&(count, s)| std::iter::repeat_n(s, count))
+                .chain(std::iter::repeat_n(0, wild))
+                .collect(),
+        };
+        Hand { kind: HandType::from_counts(&counts), key, cards: cards.to_string() }
+    }
+}
+
+fn ranked(hands: &[(String, usize)], rules: &Rules) -> Vec<(Hand, usize)> {
+    let mut hands = hands.iter().map(|(cards, bid)| (Hand::new(cards, rules), *bid)).collect::<Vec<_>>();
+    hands.sort();
+    hands
+}
+
+fn winnings(hands: &[(String, usize)], rules: &Rules) -> usize {
+    ranked(hands, rules).iter().enumerate()
+      .map(|(i, (_, bid))| (i+1) * bid)
+      .sum()
+}
+
+fn solve1(hands: &[(String, usize)]) -> usize {
+    winnings(hands, &Rules::standard())
+}
+
+fn solve2(hands: &[(String, usize)]) -> usize {
+    winnings(hands, &Rules::jokers())
+}
+
+struct Options {
+    rules: Rules,
+    types: Option<String>,
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options { rules: Rules::standard(), types: None };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{arg} needs a value"));
+        match arg.as_str() {
+            "--wild" => opts.rules.wild = value().chars().collect(),
+            "--tie-break" => opts.rules.tie_break = match value().as_str() {
+                "in-order" => TieBreak::InOrder,
+                "grouped" => TieBreak::Grouped,
+                _ => panic!("Tie break is in-order or grouped"),
+            },
+            "--types" => opts.types = Some(value()),
+            _ => panic!("Unknown argument {arg}"),
+        }
+    }
+    opts
 }
 
 fn main() {
+    let opts = parse_args();
+    if let Some(file) = &opts.types {
+        let hands = parse(file);
+        for (rank, (hand, bid)) in ranked(&hands, &opts.rules).iter().enumerate() {
+            println!("{:>5} {} {:>5} {}", rank + 1, hand.cards, bid, hand.kind);
+        }
+        println!("winnings: {}", winnings(&hands, &opts.rules));
+        return;
+    }
+
     let ex1 = parse("inputs/day7_ex1.txt");
     let inp1 = parse("inputs/day7.txt");
 
     println!("ex1: {}", solve1(&ex1));
-    println!("sol1: {}", solve1(&inp1));
+    println!("sol1: {}", part("sol1", || solve1(&inp1)));
     println!("ex2: {}", solve2(&ex1));
-    println!("sol2: {}", solve2(&inp1));
-}
\ No newline at end of file
+    println!("sol2: {}", part("sol2", || solve2(&inp1)));
+}
+
+#[test]
+fn test_hands() {
+    let kinds = |rules: &Rules| parse("inputs/day7_ex1.txt").iter()
+        .map(|(cards, _)| Hand::new(cards, rules).kind)
+        .collect::<Vec<_>>();
+    use HandType::*;
+    assert_eq!(vec![OnePair, ThreeOfAKind, TwoPair, TwoPair, ThreeOfAKind], kinds(&Rules::standard()));
+    assert_eq!(vec![OnePair, FourOfAKind, TwoPair, FourOfAKind, FourOfAKind], kinds(&Rules::jokers()));
+
+    assert_eq!(FiveOfAKind, Hand::new("JJJJJ", &Rules::jokers()).kind);
+    assert_eq!(FullHouse, Hand::new("2233J", &Rules::jokers()).kind);
+    assert_eq!(HighCard, Hand::new("23456", &Rules::jokers()).kind);
+    assert_eq!("full house", FullHouse.to_string());
+
+    let standard = Rules::standard();
+    assert!(Hand::new("33332", &standard) > Hand::new("2AAAA", &standard));
+    assert!(Hand::new("KK677", &standard) > Hand::new("KTJJT", &standard));
+    // jokers are the weakest card
+    let jokers = Rules::jokers();
+    assert!(Hand::new("JKKK2", &jokers) < Hand::new("QQQQ2", &jokers));
+
+    let grouped = Rules { tie_break: TieBreak::Grouped, ..Rules::standard() };
+    assert!(Hand::new("33332", &grouped) < Hand::new("2AAAA", &grouped));
+    assert!(Hand::new("KTJJT", &grouped) < Hand::new("TK5TK", &grouped));
+    let deuces = Rules { wild: vec!['2'], tie_break: TieBreak::Grouped };
+    assert_eq!(FourOfAKind, Hand::new("2AAA3", &deuces).kind);
+    assert!(Hand::new("2AAA3", &deuces) < Hand::new("AAAA3", &deuces));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day7_ex1.txt"): |f| solve1(&parse(f)) => 6440;
+    sol1("inputs/day7.txt"): |f| solve1(&parse(f)) => 249638405;
+    ex2("inputs/day7_ex1.txt"): |f| solve2(&parse(f)) => 5905;
+    sol2("inputs/day7.txt"): |f| solve2(&parse(f)) => 249776650;
+}
@@ -1,5 +1,6 @@
 use std::{collections::HashMap, fs};
 
+use aoc2023::{dot::{self, Dot}, stats::part};
 use nom::InputTake;
 
 fn parse(filename: &str) -> (String, HashMap<String, (String, String)>) {
@@ -146,17 +147,44 @@ fn step(pos: usize, i: usize, dirs: &[u8], left: &Vec<usize>, right: &Vec<usize>
     }
 }
 
+// Start nodes are boxes and goal nodes double circles, the edges are labelled with their direction
+fn to_dot((_, map): &(String, HashMap<String, (String, String)>)) -> Dot {
+    let mut dot = Dot::digraph("day8");
+    let mut names: Vec<&String> = map.keys().collect();
+    names.sort();
+    for name in names {
+        if name.ends_with('A') {
+            dot.node(name).shape("box");
+        } else if name.ends_with('Z') {
+            dot.node(name).shape("doublecircle");
+        }
+        let (left, right) = &map[name];
+        dot.edge(name, left).label("L");
+        dot.edge(name, right).label("R");
+    }
+    dot
+}
+
 fn main() {
     let ex1 = parse("inputs/day8_ex1.txt");
     let ex2 = parse("inputs/day8_ex2.txt");
     let inp = parse("inputs/day8.txt");
     let ex3 = parse2("inputs/day8_ex3.txt");
     let inp2 = parse2("inputs/day8.txt");
+    dot::export("day8", || to_dot(&inp));
 
     println!("ex1: {}", solve1(&ex1));
     println!("ex2: {}", solve1(&ex2));
-    println!("sol1: {}", solve1(&inp));
+    println!("sol1: {}", part("sol1", || solve1(&inp)));
 
     println!("ex3: {}", solve2(&ex3));
-    println!("sol2: {}", solve2(&inp2));
-}
\ No newline at end of file
+    println!("sol2: {}", part("sol2", || solve2(&inp2)));
+}
+
+aoc2023::answers! {
+    ex1("inputs/day8_ex1.txt"): |f| solve1(&parse(f)) => 2;
+    ex2("inputs/day8_ex2.txt"): |f| solve1(&parse(f)) => 6;
+    sol1("inputs/day8.txt"): |f| solve1(&parse(f)) => 22357;
+    ex3("inputs/day8_ex3.txt"): |f| solve2(&parse2(f)) => 6;
+    sol2("inputs/day8.txt"): |f| solve2(&parse2(f)) => 10371555451871;
+}
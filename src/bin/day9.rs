@@ -1,5 +1,7 @@
 use std::fs;
 
+use aoc2023::stats::part;
+
 fn parse(filename: &str) -> Vec<Vec<i32>> {
     fs::read_to_string(filename).unwrap().trim().lines()
         .map(|line| line.split(' ').map(|n| n.parse::<i32>().unwrap()).collect())
@@ -20,7 +22,16 @@ fn solve1(numbers: &Vec<Vec<i32>>) -> i32 {
 
 fn main() {
     let mut numbers = parse("inputs/day9.txt");
-    println!("sol1: {}", solve1(&numbers));
+    println!("sol1: {}", part("sol1", || solve1(&numbers)));
     numbers.iter_mut().for_each(|xs| xs.reverse());
-    println!("sol2: {}", solve1(&numbers));
-}
\ No newline at end of file
+    println!("sol2: {}", part("sol2", || solve1(&numbers)));
+}
+
+aoc2023::answers! {
+    sol1("inputs/day9.txt"): |f| solve1(&parse(f)) => 1934898178;
+    sol2("inputs/day9.txt"): |f| {
+        let mut numbers = parse(f);
+        numbers.iter_mut().for_each(|xs| xs.reverse());
+        solve1(&numbers)
+    } => 1129;
+}
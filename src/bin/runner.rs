@@ -0,0 +1,155 @@
+// Runs the day binaries concurrently and prints their output in day order.
+// The day binaries are expected to be built next to the runner, see run-all.sh.
+// Each part's time is reported too, and its allocations when built with `--features alloc-stats`.
+// Anything else the days write to stderr is passed on.
+// With `--dot DIR` the days with a graph shaped puzzle also write it to DIR/dayN.dot, the directory
+// being passed on in the AOC_DOT environment variable.
+//
+// Usage: runner [-j N] [--dot DIR] [DAY...]
+use std::{env, fs, path::PathBuf, process::{Command, Output}, time::{Duration, Instant}};
+
+use aoc2023::{dot, parallel::{par_map_n, threads}, stats};
+
+struct Options {
+    jobs: usize,
+    dot: Option<PathBuf>,
+    days: Vec<usize>,
+}
+
+fn parse_args() -> Options {
+    let mut jobs = threads();
+    let mut dot = None;
+    let mut days = vec![];
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-j" | "--jobs" => {
+                jobs = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage());
+            }
+            "--dot" => {
+                dot = Some(args.next().map(PathBuf::from).unwrap_or_else(|| usage()));
+            }
+            day => days.push(day.parse().unwrap_or_else(|_| usage())),
+        }
+    }
+    if days.is_empty() {
+        days = (1..=25).collect();
+    }
+    Options { jobs, dot, days }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: runner [-j N] [--dot DIR] [DAY...]");
+    std::process::exit(2);
+}
+
+fn bin_path(day: usize) -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.with_file_name(format!("day{day}"))
+}
+
+fn run(day: usize, dot: Option<&PathBuf>) -> (Output, Duration) {
+    let mut cmd = Command::new(bin_path(day));
+    cmd.env(stats::ENV, "1");
+    if let Some(dir) = dot {
+        cmd.env(dot::ENV, dir);
+    }
+
+    let start = Instant::now();
+    let output = cmd.output().unwrap_or_else(|e| {
+        panic!("Could not run day{day}: {e}")
+    });
+    (output, start.elapsed())
+}
+
+struct PartStats {
+    name: String,
+    values: Vec<(String, usize)>,
+}
+
+// Parse the lines written by `stats::part`, e.g. "stats sol1 time_us=1234 peak=100 total=200 count=3"
+fn part_stats(stderr: &str) -> Vec<PartStats> {
+    stderr.lines().filter_map(|line| {
+        let mut words = line.split_whitespace();
+        if words.next() != Some(stats::PREFIX) {
+            return None;
+        }
+        let name = words.next()?.to_string();
+        let values = words.filter_map(|word| {
+            let (key, value) = word.split_once('=')?;
+            Some((key.to_string(), value.parse().ok()?))
+        }).collect();
+        Some(PartStats { name, values })
+    }).collect()
+}
+
+// Everything else a day wrote to stderr, like warnings and diagnostics
+fn other_lines(stderr: &str) -> impl Iterator<Item = &str> {
+    stderr.lines().filter(|line| line.split_whitespace().next() != Some(stats::PREFIX))
+}
+
+fn fmt_bytes(n: usize) -> String {
+    match n {
+        n if n >= 1 << 30 => format!("{:.1} GiB", n as f64 / (1 << 30) as f64),
+        n if n >= 1 << 20 => format!("{:.1} MiB", n as f64 / (1 << 20) as f64),
+        n if n >= 1 << 10 => format!("{:.1} KiB", n as f64 / (1 << 10) as f64),
+        n => format!("{n} B"),
+    }
+}
+
+fn print_stats(parts: &[PartStats]) {
+    for part in parts {
+        let mut line = format!("   {:<8}", part.name);
+        for (key, value) in part.values.iter() {
+            let value = match key.as_str() {
+                "time_us" => format!("{:.1} ms", *value as f64 / 1000.0),
+                "peak" | "total" => fmt_bytes(*value),
+                _ => value.to_string(),
+            };
+            let key = key.strip_suffix("_us").unwrap_or(key);
+            line += &format!(" {key} {value:>10}");
+        }
+        println!("{line}");
+    }
+}
+
+fn main() {
+    let opts = parse_args();
+    if let Some(dir) = &opts.dot {
+        fs::create_dir_all(dir).unwrap_or_else(|e| panic!("Could not create {dir:?}: {e}"));
+    }
+
+    let start = Instant::now();
+    let results = par_map_n(&opts.days, opts.jobs, |&day| run(day, opts.dot.as_ref()));
+
+    let mut failed = vec![];
+    for (day, (output, time)) in opts.days.iter().zip(results) {
+        println!("== day{day} ({} ms)", time.as_millis());
+        print!("{}", String::from_utf8_lossy(&output.stdout));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        print_stats(&part_stats(&stderr));
+        // the stats lines were printed above
+        other_lines(&stderr).for_each(|line| eprintln!("{line}"));
+        if !output.status.success() {
+            failed.push(day);
+        }
+    }
+    println!("== total ({} ms, {} jobs)", start.elapsed().as_millis(), opts.jobs);
+
+    if !failed.is_empty() {
+        eprintln!("failed: {failed:?}");
+        std::process::exit(1);
+    }
+}
+
+#[test]
+fn test_part_stats() {
+    let stderr = "warning: slow\nstats sol1 time_us=1234 peak=100 total=200 count=3\nstats sol2 time_us=5\nstatsish line\n";
+    let parts = part_stats(stderr);
+    assert_eq!(vec!["sol1", "sol2"], parts.iter().map(|p| p.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(vec![("time_us".to_string(), 1234), ("peak".to_string(), 100), ("total".to_string(), 200),
+                    ("count".to_string(), 3)], parts[0].values);
+    assert_eq!(vec![("time_us".to_string(), 5)], parts[1].values);
+    assert_eq!(vec!["warning: slow", "statsish line"], other_lines(stderr).collect::<Vec<_>>());
+}
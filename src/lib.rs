@@ -1,7 +1,5 @@
 pub mod coord {
-    use std::ops::{Add, Sub, Mul};
-
-    use itertools::Itertools;
+    use std::{fmt, ops::{Add, Sub, Mul}, str::FromStr};
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct Coord {
@@ -43,6 +41,19 @@ pub mod coord {
             }
         }
 
+        pub fn dir8(d: Dir8) -> Coord {
+            match d {
+                Dir8::Right => Self::right(),
+                Dir8::DownRight => Self::down() + Self::right(),
+                Dir8::Down => Self::down(),
+                Dir8::DownLeft => Self::down() + Self::left(),
+                Dir8::Left => Self::left(),
+                Dir8::UpLeft => Self::up() + Self::left(),
+                Dir8::Up => Self::up(),
+                Dir8::UpRight => Self::up() + Self::right(),
+            }
+        }
+
         pub fn x_u(&self) -> usize {
             self.x.try_into().unwrap()
         }
@@ -55,15 +66,25 @@ pub mod coord {
             (self.x.abs() + self.y.abs()).try_into().unwrap()
         }
 
-        pub fn neighbours(&self) -> Vec<Coord> {
-            (0..4).map(|d| {
-                *self + Coord::dir(Dir::of_id(d))
-            }).collect_vec()
+        // The 4 orthogonal neighbours
+        pub fn neighbours(&self) -> impl Iterator<Item = Coord> {
+            let pos = *self;
+            (0..4).map(move |d| pos.go(Dir::of_id(d)))
+        }
+
+        // All 8 neighbours, including the diagonal ones
+        pub fn neighbours8(&self) -> impl Iterator<Item = Coord> {
+            let pos = *self;
+            (0..8).map(move |d| pos.go8(Dir8::of_id(d)))
         }
-        
+
         pub fn go(&self, d: Dir) -> Coord {
             *self + Coord::dir(d)
         }
+
+        pub fn go8(&self, d: Dir8) -> Coord {
+            *self + Coord::dir8(d)
+        }
     }
 
     impl Add for Coord {
@@ -90,7 +111,7 @@ pub mod coord {
         }
     }
 
-    #[derive(Hash, PartialEq, Eq, Clone, Copy)]
+    #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
     pub enum Dir {
         Right,
         Down,
@@ -125,11 +146,206 @@ pub mod coord {
         pub fn turn_right(self) -> Dir {
             Self::of_id((self.id() + 1) % 4)
         }
+
+        pub const ALL: [Dir; 4] = [Dir::Right, Dir::Down, Dir::Left, Dir::Up];
+
+        pub fn all() -> impl Iterator<Item = Dir> {
+            Self::ALL.into_iter()
+        }
+
+        pub fn opposite(self) -> Dir {
+            Self::of_id((self.id() + 2) % 4)
+        }
+
+        pub fn is_horizontal(self) -> bool {
+            self == Dir::Right || self == Dir::Left
+        }
+
+        pub fn is_vertical(self) -> bool {
+            !self.is_horizontal()
+        }
+
+        pub fn to_char(self, notation: Notation) -> char {
+            notation.chars()[self.id()]
+        }
+
+        pub fn from_char(c: char, notation: Notation) -> Option<Dir> {
+            notation.chars().iter().position(|&x| x == c).map(Dir::of_id)
+        }
+
+        // e.g. `format!("{}", dir.display(Notation::Arrow))`
+        pub fn display(self, notation: Notation) -> DirDisplay {
+            DirDisplay { dir: self, notation }
+        }
+    }
+
+    // The different ways the puzzles write directions
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum Notation {
+        Letter,  // R D L U
+        Arrow,   // > v < ^
+        Compass, // E S W N
+        Numeric, // 0 1 2 3
+    }
+
+    impl Notation {
+        pub const ALL: [Notation; 4] = [Notation::Letter, Notation::Arrow, Notation::Compass, Notation::Numeric];
+
+        // Indexed by direction id
+        fn chars(self) -> [char; 4] {
+            match self {
+                Notation::Letter => ['R', 'D', 'L', 'U'],
+                Notation::Arrow => ['>', 'v', '<', '^'],
+                Notation::Compass => ['E', 'S', 'W', 'N'],
+                Notation::Numeric => ['0', '1', '2', '3'],
+            }
+        }
+    }
+
+    pub struct DirDisplay {
+        dir: Dir,
+        notation: Notation,
+    }
+
+    impl fmt::Display for DirDisplay {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.dir.to_char(self.notation))
+        }
+    }
+
+    // Letter notation by default
+    impl fmt::Display for Dir {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.to_char(Notation::Letter))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseDirError(String);
+
+    impl fmt::Display for ParseDirError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid direction: {:?}", self.0)
+        }
+    }
+
+    impl std::error::Error for ParseDirError {}
+
+    // Accepts any of the notations, they don't share any characters
+    impl TryFrom<char> for Dir {
+        type Error = ParseDirError;
+
+        fn try_from(c: char) -> Result<Self, Self::Error> {
+            Notation::ALL.iter()
+                .find_map(|&notation| Dir::from_char(c, notation))
+                .ok_or_else(|| ParseDirError(c.to_string()))
+        }
+    }
+
+    impl FromStr for Dir {
+        type Err = ParseDirError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Dir::try_from(c),
+                _ => Err(ParseDirError(s.to_string())),
+            }
+        }
+    }
+
+    // Directions including the diagonals, in clockwise order starting to the right
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+    pub enum Dir8 {
+        Right,
+        DownRight,
+        Down,
+        DownLeft,
+        Left,
+        UpLeft,
+        Up,
+        UpRight,
+    }
+
+    impl Dir8 {
+        pub fn of_id(id: usize) -> Dir8 {
+            match id {
+                0 => Dir8::Right,
+                1 => Dir8::DownRight,
+                2 => Dir8::Down,
+                3 => Dir8::DownLeft,
+                4 => Dir8::Left,
+                5 => Dir8::UpLeft,
+                6 => Dir8::Up,
+                7 => Dir8::UpRight,
+                _ => panic!("Invalid direction id: {id}"),
+            }
+        }
+
+        pub fn id(self) -> usize {
+            match self {
+                Dir8::Right => 0,
+                Dir8::DownRight => 1,
+                Dir8::Down => 2,
+                Dir8::DownLeft => 3,
+                Dir8::Left => 4,
+                Dir8::UpLeft => 5,
+                Dir8::Up => 6,
+                Dir8::UpRight => 7,
+            }
+        }
+
+        // Rotate 45 degrees counter-clockwise
+        pub fn rotate_left(self) -> Dir8 {
+            Self::of_id((self.id() + 7) % 8)
+        }
+
+        // Rotate 45 degrees clockwise
+        pub fn rotate_right(self) -> Dir8 {
+            Self::of_id((self.id() + 1) % 8)
+        }
+
+        pub fn opposite(self) -> Dir8 {
+            Self::of_id((self.id() + 4) % 8)
+        }
+
+        pub fn is_diagonal(self) -> bool {
+            self.id() % 2 == 1
+        }
+
+        // The cardinal direction, or None for diagonals
+        pub fn cardinal(self) -> Option<Dir> {
+            (!self.is_diagonal()).then(|| Dir::of_id(self.id() / 2))
+        }
+    }
+
+    impl From<Dir> for Dir8 {
+        fn from(d: Dir) -> Self {
+            Dir8::of_id(d.id() * 2)
+        }
+    }
+
+    impl TryFrom<Dir8> for Dir {
+        type Error = Dir8;
+
+        fn try_from(d: Dir8) -> Result<Self, Self::Error> {
+            d.cardinal().ok_or(d)
+        }
     }
 
     pub trait CoordMap<T> {
         fn at(&self, p: Coord) -> &T;
         fn contains(&self, p: Coord) -> bool;
+
+        // The orthogonal neighbours of p that are inside the map
+        fn neighbours(&self, p: Coord) -> impl Iterator<Item = Coord> + '_ where Self: Sized {
+            p.neighbours().filter(move |&n| self.contains(n))
+        }
+
+        // All 8 neighbours of p that are inside the map
+        fn neighbours8(&self, p: Coord) -> impl Iterator<Item = Coord> + '_ where Self: Sized {
+            p.neighbours8().filter(move |&n| self.contains(n))
+        }
     }
 
     impl<T> CoordMap<T> for Vec<Vec<T>> {
@@ -176,4 +392,1271 @@ pub mod coord {
             self.masks[i] &= !v;
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dir_notations() {
+        for notation in Notation::ALL {
+            for d in Dir::all() {
+                let s = d.display(notation).to_string();
+                assert_eq!(Ok(d), s.parse::<Dir>());
+                assert_eq!(Some(d.opposite()), Dir::from_char(d.opposite().to_char(notation), notation));
+            }
+        }
+        assert_eq!(Ok(Dir::Down), Dir::try_from('v'));
+        assert!("RD".parse::<Dir>().is_err());
+        assert!(Dir::try_from('x').is_err());
+    }
+
+    #[test]
+    fn test_dir8() {
+        assert_eq!(Dir8::UpRight, Dir8::Right.rotate_left());
+        assert_eq!(Dir8::Right, Dir8::UpRight.rotate_right());
+        assert_eq!(Dir8::DownLeft, Dir8::UpRight.opposite());
+        for id in 0..8 {
+            let d = Dir8::of_id(id);
+            assert_eq!(d, d.rotate_left().rotate_right());
+            assert_eq!(d.opposite(), (0..4).fold(d, |d, _| d.rotate_right()));
+            assert_eq!(d, d.opposite().opposite());
+            assert_eq!(Coord::new(0, 0), Coord::dir8(d) + Coord::dir8(d.opposite()));
+            assert_eq!(d.is_diagonal(), Coord::dir8(d).manhattan() == 2);
+        }
+
+        for d in Dir::all() {
+            let d8 = Dir8::from(d);
+            assert_eq!(Some(d), d8.cardinal());
+            assert_eq!(Ok(d), Dir::try_from(d8));
+            assert_eq!(Coord::dir(d), Coord::dir8(d8));
+        }
+        assert_eq!(None, Dir8::DownLeft.cardinal());
+        assert_eq!(Err(Dir8::DownLeft), Dir::try_from(Dir8::DownLeft));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let p = Coord::new(3, -2);
+        let mut around: Vec<(i64, i64)> = p.neighbours8().map(|n| (n.x, n.y)).collect();
+        around.sort();
+        assert_eq!(vec![(2, -3), (2, -2), (2, -1), (3, -3), (3, -1), (4, -3), (4, -2), (4, -1)], around);
+        assert!(p.neighbours().all(|n| (n - p).manhattan() == 1));
+        assert_eq!(4, p.neighbours().count());
+
+        let map = vec![vec!['.'; 3]; 2];
+        let sorted = |it: &mut dyn Iterator<Item = Coord>| {
+            let mut ns: Vec<(i64, i64)> = it.map(|n| (n.x, n.y)).collect();
+            ns.sort();
+            ns
+        };
+        assert_eq!(vec![(0, 1), (1, 0)], sorted(&mut map.neighbours(Coord::new(0, 0))));
+        assert_eq!(vec![(0, 1), (1, 0), (1, 1)], sorted(&mut map.neighbours8(Coord::new(0, 0))));
+        assert_eq!(vec![(1, 0), (2, 1)], sorted(&mut map.neighbours(Coord::new(2, 0))));
+        assert_eq!(vec![(0, 0), (0, 1), (1, 1), (2, 0), (2, 1)], sorted(&mut map.neighbours8(Coord::new(1, 0))));
+        assert_eq!(vec![(1, 1), (2, 0)], sorted(&mut map.neighbours(Coord::new(2, 1))));
+        assert_eq!(vec![(1, 0), (1, 1), (2, 0)], sorted(&mut map.neighbours8(Coord::new(2, 1))));
+    }
+}
+
+pub mod graph {
+    // Graphs with nodes 0..n, named through an `Interner` when the input refers to them by name.
+    // The minimum cuts take undirected edges (a, b, weight).
+    use std::collections::{HashMap, VecDeque};
+
+    use priority_queue::PriorityQueue;
+
+    // Gives each name an id, in the order they are first seen
+    #[derive(Debug, Clone, Default)]
+    pub struct Interner {
+        names: Vec<String>,
+        ids: HashMap<String, usize>,
+    }
+
+    impl Interner {
+        pub fn new() -> Interner {
+            Interner::default()
+        }
+
+        pub fn intern(&mut self, name: &str) -> usize {
+            if let Some(&id) = self.ids.get(name) {
+                return id;
+            }
+            self.names.push(name.to_string());
+            self.ids.insert(name.to_string(), self.names.len() - 1);
+            self.names.len() - 1
+        }
+
+        pub fn id(&self, name: &str) -> Option<usize> {
+            self.ids.get(name).copied()
+        }
+
+        pub fn name(&self, id: usize) -> &str {
+            &self.names[id]
+        }
+
+        pub fn names(&self) -> &[String] {
+            &self.names
+        }
+
+        pub fn len(&self) -> usize {
+            self.names.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.names.is_empty()
+        }
+    }
+
+    // Adjacency lists indexed by the interned ids
+    #[derive(Debug, Clone, Default)]
+    pub struct NamedGraph {
+        pub names: Interner,
+        pub adj: Vec<Vec<usize>>,
+    }
+
+    impl NamedGraph {
+        pub fn new() -> NamedGraph {
+            NamedGraph::default()
+        }
+
+        pub fn node(&mut self, name: &str) -> usize {
+            let id = self.names.intern(name);
+            if id == self.adj.len() {
+                self.adj.push(vec![]);
+            }
+            id
+        }
+
+        pub fn add_edge(&mut self, a: &str, b: &str) -> (usize, usize) {
+            let (a, b) = (self.node(a), self.node(b));
+            self.adj[a].push(b);
+            (a, b)
+        }
+
+        pub fn add_undirected(&mut self, a: &str, b: &str) -> (usize, usize) {
+            let (a, b) = self.add_edge(a, b);
+            self.adj[b].push(a);
+            (a, b)
+        }
+
+        pub fn name(&self, id: usize) -> &str {
+            self.names.name(id)
+        }
+
+        pub fn len(&self) -> usize {
+            self.adj.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.adj.is_empty()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Cut {
+        pub weight: u64,
+        pub partition: (Vec<usize>, Vec<usize>),
+        pub edges: Vec<(usize, usize)>, // the edges crossing the cut
+    }
+
+    impl Cut {
+        // The cut between the nodes with in_first set and the rest
+        pub fn from_sides(n: usize, edges: &[(usize, usize, u64)], in_first: &[bool]) -> Cut {
+            let first = (0..n).filter(|&v| in_first[v]).collect();
+            let second = (0..n).filter(|&v| !in_first[v]).collect();
+            let crossing: Vec<(usize, usize, u64)> = edges.iter().copied().filter(|&(a, b, _)| in_first[a] != in_first[b]).collect();
+            Cut {
+                weight: crossing.iter().map(|&(_, _, w)| w).sum(),
+                partition: (first, second),
+                edges: crossing.iter().map(|&(a, b, _)| (a, b)).collect(),
+            }
+        }
+    }
+
+    // Edges with weight 1 from adjacency lists, each edge only once even if it's listed from both ends
+    pub fn unit_edges(adj: &[Vec<usize>]) -> Vec<(usize, usize, u64)> {
+        let mut edges: Vec<(usize, usize, u64)> = adj.iter().enumerate()
+            .flat_map(|(a, bs)| bs.iter().map(move |&b| (a.min(b), a.max(b), 1)))
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    // Global minimum cut using Stoer-Wagner. Every phase grows a set by repeatedly adding the most tightly
+    // connected node, and the last two nodes added are merged. The cut between the last node and the
+    // rest is the minimum cut separating those two nodes, so the best cut of any phase is the global minimum.
+    pub fn stoer_wagner(n: usize, edges: &[(usize, usize, u64)]) -> Cut {
+        assert!(n >= 2, "Need at least two nodes to cut");
+        let mut adj: Vec<HashMap<usize, u64>> = vec![HashMap::new(); n];
+        for &(a, b, w) in edges {
+            if a != b {
+                *adj[a].entry(b).or_insert(0) += w;
+                *adj[b].entry(a).or_insert(0) += w;
+            }
+        }
+        let mut members: Vec<Vec<usize>> = (0..n).map(|v| vec![v]).collect();
+        let mut active: Vec<usize> = (0..n).collect();
+
+        let mut best: Option<(u64, Vec<usize>)> = None;
+        while active.len() > 1 {
+            let mut queue: PriorityQueue<usize, u64> = active.iter().map(|&v| (v, 0)).collect();
+            let mut prev = active[0];
+            let mut last = active[0];
+            let mut last_weight = 0;
+            while let Some((v, w)) = queue.pop() {
+                prev = last;
+                last = v;
+                last_weight = w;
+                for (&u, &uw) in adj[v].iter() {
+                    queue.change_priority_by(&u, |p| *p += uw);
+                }
+            }
+
+            if !matches!(best, Some((w, _)) if w <= last_weight) {
+                best = Some((last_weight, members[last].clone()));
+            }
+
+            // merge last into prev
+            let last_adj = std::mem::take(&mut adj[last]);
+            for (u, w) in last_adj {
+                adj[u].remove(&last);
+                if u != prev {
+                    *adj[prev].entry(u).or_insert(0) += w;
+                    *adj[u].entry(prev).or_insert(0) += w;
+                }
+            }
+            let last_members = std::mem::take(&mut members[last]);
+            members[prev].extend(last_members);
+            active.retain(|&v| v != last);
+        }
+
+        let mut in_first = vec![false; n];
+        best.unwrap().1.into_iter().for_each(|v| in_first[v] = true);
+        Cut::from_sides(n, edges, &in_first)
+    }
+
+    // Minimum cut separating s and t, from the maximum flow found by Edmonds-Karp. The first part of the
+    // partition is the side of s.
+    pub fn min_st_cut(n: usize, edges: &[(usize, usize, u64)], s: usize, t: usize) -> Cut {
+        assert!(s != t);
+        // residual arcs, arc i ^ 1 is the reverse of arc i
+        let mut to = vec![];
+        let mut cap = vec![];
+        let mut out: Vec<Vec<usize>> = vec![vec![]; n];
+        for &(a, b, w) in edges {
+            out[a].push(to.len());
+            to.push(b);
+            cap.push(w);
+            out[b].push(to.len());
+            to.push(a);
+            cap.push(w);
+        }
+
+        loop {
+            // shortest augmenting path
+            let mut via: Vec<Option<usize>> = vec![None; n];
+            let mut queue = VecDeque::from([s]);
+            while let Some(v) = queue.pop_front() {
+                for &arc in out[v].iter() {
+                    let u = to[arc];
+                    if cap[arc] > 0 && u != s && via[u].is_none() {
+                        via[u] = Some(arc);
+                        queue.push_back(u);
+                    }
+                }
+            }
+
+            if via[t].is_none() {
+                let mut in_first: Vec<bool> = via.iter().map(|arc| arc.is_some()).collect();
+                in_first[s] = true;
+                return Cut::from_sides(n, edges, &in_first);
+            }
+
+            let mut path = vec![];
+            let mut v = t;
+            while let Some(arc) = via[v] {
+                path.push(arc);
+                v = to[arc ^ 1];
+            }
+            let flow = path.iter().map(|&arc| cap[arc]).min().unwrap();
+            for arc in path {
+                cap[arc] -= flow;
+                cap[arc ^ 1] += flow;
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_cut() {
+        // two heavy triangles joined by the edges 2-3 and 0-5
+        let edges = vec![(0, 1, 2), (1, 2, 2), (2, 0, 2), (3, 4, 2), (4, 5, 2), (5, 3, 2), (2, 3, 1), (0, 5, 1)];
+        let cut = stoer_wagner(6, &edges);
+        assert_eq!(2, cut.weight);
+        assert_eq!(vec![(2, 3), (0, 5)], cut.edges);
+        assert_eq!(3, cut.partition.0.len());
+
+        let cut = min_st_cut(6, &edges, 1, 4);
+        assert_eq!(2, cut.weight);
+        assert_eq!((vec![0, 1, 2], vec![3, 4, 5]), cut.partition);
+
+        let edges = vec![(0, 1, 5), (1, 2, 1), (2, 3, 5), (0, 3, 1), (0, 2, 4)];
+        assert_eq!(6, stoer_wagner(4, &edges).weight);
+        assert_eq!(6, min_st_cut(4, &edges, 3, 0).weight);
+    }
+
+    #[test]
+    fn test_named_graph() {
+        let mut g = NamedGraph::new();
+        g.add_undirected("jqt", "rhn");
+        g.add_undirected("rhn", "xhk");
+        g.add_edge("xhk", "jqt");
+        assert_eq!(3, g.len());
+        assert_eq!(Some(1), g.names.id("rhn"));
+        assert_eq!(None, g.names.id("abc"));
+        assert_eq!("xhk", g.name(2));
+        assert_eq!(vec![vec![1], vec![0, 2], vec![1, 0]], g.adj);
+    }
+}
+
+pub mod dot {
+    // Graphviz DOT output, for looking at the graph shaped puzzles. The days write their graph to
+    // DIR/dayN.dot when given `--dot DIR` or AOC_DOT=DIR, render it with e.g. `dot -Tsvg day20.dot > day20.svg`
+    use std::{env, fmt, fs, path::PathBuf};
+
+    pub struct Dot {
+        name: String,
+        directed: bool,
+        nodes: Vec<Node>,
+        edges: Vec<Edge>,
+    }
+
+    pub struct Node {
+        id: String,
+        attrs: Vec<(String, String)>,
+    }
+
+    pub struct Edge {
+        from: String,
+        to: String,
+        attrs: Vec<(String, String)>,
+    }
+
+    impl Dot {
+        pub fn digraph(name: &str) -> Dot {
+            Dot { name: name.to_string(), directed: true, nodes: vec![], edges: vec![] }
+        }
+
+        pub fn graph(name: &str) -> Dot {
+            Dot { directed: false, ..Dot::digraph(name) }
+        }
+
+        // Nodes only need to be added to give them attributes, edges can refer to any id
+        pub fn node(&mut self, id: impl fmt::Display) -> &mut Node {
+            self.nodes.push(Node { id: id.to_string(), attrs: vec![] });
+            self.nodes.last_mut().unwrap()
+        }
+
+        pub fn edge(&mut self, from: impl fmt::Display, to: impl fmt::Display) -> &mut Edge {
+            self.edges.push(Edge { from: from.to_string(), to: to.to_string(), attrs: vec![] });
+            self.edges.last_mut().unwrap()
+        }
+
+        pub fn write(&self, path: &PathBuf) {
+            fs::write(path, self.to_string()).unwrap_or_else(|e| panic!("Could not write {path:?}: {e}"));
+        }
+    }
+
+    impl Node {
+        pub fn attr(&mut self, key: &str, value: impl fmt::Display) -> &mut Node {
+            self.attrs.push((key.to_string(), value.to_string()));
+            self
+        }
+
+        pub fn label(&mut self, label: impl fmt::Display) -> &mut Node {
+            self.attr("label", label)
+        }
+
+        pub fn shape(&mut self, shape: &str) -> &mut Node {
+            self.attr("shape", shape)
+        }
+    }
+
+    impl Edge {
+        pub fn attr(&mut self, key: &str, value: impl fmt::Display) -> &mut Edge {
+            self.attrs.push((key.to_string(), value.to_string()));
+            self
+        }
+
+        pub fn label(&mut self, label: impl fmt::Display) -> &mut Edge {
+            self.attr("label", label)
+        }
+    }
+
+    fn quote(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+    }
+
+    fn fmt_attrs(attrs: &[(String, String)]) -> String {
+        if attrs.is_empty() {
+            return String::new();
+        }
+        let attrs: Vec<String> = attrs.iter().map(|(k, v)| format!("{k}={}", quote(v))).collect();
+        format!(" [{}]", attrs.join(", "))
+    }
+
+    impl fmt::Display for Dot {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let (kind, arrow) = if self.directed { ("digraph", "->") } else { ("graph", "--") };
+            writeln!(f, "{kind} {} {{", quote(&self.name))?;
+            for node in self.nodes.iter() {
+                writeln!(f, "    {}{};", quote(&node.id), fmt_attrs(&node.attrs))?;
+            }
+            for edge in self.edges.iter() {
+                writeln!(f, "    {} {arrow} {}{};", quote(&edge.from), quote(&edge.to), fmt_attrs(&edge.attrs))?;
+            }
+            writeln!(f, "}}")
+        }
+    }
+
+    // Set by the runner, so the days don't have to accept its `--dot` argument
+    pub const ENV: &str = "AOC_DOT";
+
+    // The directory given with `--dot DIR` or in the AOC_DOT environment variable, if any
+    pub fn dir_arg() -> Option<PathBuf> {
+        let mut args = env::args().skip_while(|arg| arg != "--dot").skip(1);
+        args.next().or_else(|| env::var(ENV).ok()).map(PathBuf::from)
+    }
+
+    // Write the graph to DIR/name.dot if the day was run with `--dot DIR`. The graph is only built then.
+    pub fn export(name: &str, graph: impl FnOnce() -> Dot) {
+        if let Some(dir) = dir_arg() {
+            let path = dir.join(format!("{name}.dot"));
+            graph().write(&path);
+            eprintln!("wrote {}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut dot = Dot::digraph("test");
+        dot.node("a").label("%a").shape("box");
+        dot.edge("a", "b");
+        dot.edge("b", "a").label("say \"hi\"");
+        assert_eq!(dot.to_string(), [
+            "digraph \"test\" {",
+            "    \"a\" [label=\"%a\", shape=\"box\"];",
+            "    \"a\" -> \"b\";",
+            "    \"b\" -> \"a\" [label=\"say \\\"hi\\\"\"];",
+            "}\n",
+        ].join("\n"));
+
+        let mut dot = Dot::graph("g");
+        dot.edge(1, 2);
+        assert_eq!(dot.to_string(), "graph \"g\" {\n    \"1\" -- \"2\";\n}\n");
+    }
+}
+
+pub mod grid {
+    use std::collections::{HashMap, HashSet, VecDeque};
+
+    use crate::coord::{Coord, CoordMap, Dir8};
+
+    // An infinite plane covered by copies of a map
+    pub struct TiledGrid<'a, T> {
+        map: &'a Vec<Vec<T>>,
+    }
+
+    impl<'a, T> TiledGrid<'a, T> {
+        pub fn new(map: &'a Vec<Vec<T>>) -> Self {
+            TiledGrid { map }
+        }
+
+        pub fn width(&self) -> i64 {
+            self.map[0].len().try_into().unwrap()
+        }
+
+        pub fn height(&self) -> i64 {
+            self.map.len().try_into().unwrap()
+        }
+
+        // The corresponding position in the original map
+        pub fn wrap(&self, p: Coord) -> Coord {
+            Coord::new(p.x.rem_euclid(self.width()), p.y.rem_euclid(self.height()))
+        }
+    }
+
+    impl<'a, T> CoordMap<T> for TiledGrid<'a, T> {
+        fn at(&self, p: Coord) -> &T {
+            self.map.at(self.wrap(p))
+        }
+
+        fn contains(&self, _p: Coord) -> bool {
+            true
+        }
+    }
+
+    // A grid over unbounded coordinates that only stores the cells that have been set. Unset cells have the
+    // default value.
+    pub struct SparseGrid<T> {
+        cells: HashMap<Coord, T>,
+        default: T,
+        bounds: Option<(Coord, Coord)>,
+    }
+
+    impl<T> SparseGrid<T> {
+        pub fn new(default: T) -> Self {
+            SparseGrid { cells: HashMap::new(), default, bounds: None }
+        }
+
+        pub fn set(&mut self, p: Coord, value: T) {
+            self.bounds = Some(match self.bounds {
+                None => (p, p),
+                Some((min, max)) => (
+                    Coord::new(min.x.min(p.x), min.y.min(p.y)),
+                    Coord::new(max.x.max(p.x), max.y.max(p.y)),
+                ),
+            });
+            self.cells.insert(p, value);
+        }
+
+        pub fn get(&self, p: Coord) -> &T {
+            self.cells.get(&p).unwrap_or(&self.default)
+        }
+
+        pub fn is_set(&self, p: Coord) -> bool {
+            self.cells.contains_key(&p)
+        }
+
+        // Number of cells that have been set
+        pub fn len(&self) -> usize {
+            self.cells.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.cells.is_empty()
+        }
+
+        // The smallest and largest coordinates set, both inclusive
+        pub fn bounds(&self) -> Option<(Coord, Coord)> {
+            self.bounds
+        }
+
+        pub fn width(&self) -> usize {
+            self.bounds.map_or(0, |(min, max)| (max.x - min.x + 1).try_into().unwrap())
+        }
+
+        pub fn height(&self) -> usize {
+            self.bounds.map_or(0, |(min, max)| (max.y - min.y + 1).try_into().unwrap())
+        }
+
+        // The set cells, row by row
+        pub fn iter(&self) -> impl Iterator<Item = (Coord, &T)> {
+            let mut keys: Vec<&Coord> = self.cells.keys().collect();
+            keys.sort_by_key(|p| (p.y, p.x));
+            keys.into_iter().map(|p| (*p, &self.cells[p]))
+        }
+
+        // All cells within the bounds, set or not, row by row
+        pub fn rows(&self) -> impl Iterator<Item = Vec<&T>> {
+            let (min, max) = self.bounds.unwrap_or((Coord::new(0, 0), Coord::new(-1, -1)));
+            (min.y..=max.y).map(move |y| (min.x..=max.x).map(|x| self.get(Coord::new(x, y))).collect())
+        }
+
+        // A dense copy where [0][0] is the minimum corner of the bounds, or None if it would have more than
+        // `max_cells` cells
+        pub fn to_dense(&self, max_cells: usize) -> Option<Vec<Vec<T>>> where T: Clone {
+            if self.width().checked_mul(self.height()).is_none_or(|n| n > max_cells) {
+                return None;
+            }
+            Some(self.rows().map(|row| row.into_iter().cloned().collect()).collect())
+        }
+
+        pub fn render(&self, f: impl Fn(&T) -> char) -> String {
+            self.rows().map(|row| row.into_iter().map(&f).chain(['\n']).collect::<String>()).collect()
+        }
+    }
+
+    impl<T> CoordMap<T> for SparseGrid<T> {
+        fn at(&self, p: Coord) -> &T {
+            self.get(p)
+        }
+
+        fn contains(&self, p: Coord) -> bool {
+            self.bounds.is_some_and(|(min, max)| p.x >= min.x && p.y >= min.y && p.x <= max.x && p.y <= max.y)
+        }
+    }
+
+    // Breadth first search from all of `starts`, walking at most `max_dist` steps to orthogonal
+    // neighbours that are `passable`. Returns the distance to every position reached.
+    pub fn bfs<T, M, F>(map: &M, starts: &[Coord], max_dist: usize, passable: F) -> HashMap<Coord, usize>
+    where M: CoordMap<T>, F: Fn(&T) -> bool {
+        let mut dists = HashMap::new();
+        let mut queue = VecDeque::new();
+        for &start in starts {
+            if dists.insert(start, 0).is_none() {
+                queue.push_back(start);
+            }
+        }
+
+        while let Some(pos) = queue.pop_front() {
+            let dist = dists[&pos];
+            if dist == max_dist {
+                continue;
+            }
+            for n in map.neighbours(pos) {
+                if !dists.contains_key(&n) && passable(map.at(n)) {
+                    dists.insert(n, dist + 1);
+                    queue.push_back(n);
+                }
+            }
+        }
+        dists
+    }
+
+    // Like `bfs` but for a bounded map, with None for the positions that can't be reached
+    pub fn distance_map<T>(map: &Vec<Vec<T>>, starts: &[Coord], passable: impl Fn(&T) -> bool) -> Vec<Vec<Option<usize>>> {
+        let mut res = vec![vec![None; map[0].len()]; map.len()];
+        for (p, dist) in bfs(map, starts, usize::MAX, passable) {
+            res[p.y_u()][p.x_u()] = Some(dist);
+        }
+        res
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Connectivity {
+        Four,
+        Eight,
+    }
+
+    impl Connectivity {
+        pub fn neighbours(self, p: Coord) -> impl Iterator<Item = Coord> {
+            let step = match self {
+                Connectivity::Four => 2, // skip the diagonals
+                Connectivity::Eight => 1,
+            };
+            (0..8).step_by(step).map(move |d| p.go8(Dir8::of_id(d)))
+        }
+    }
+
+    // All positions reachable from any of `starts` through passable cells
+    pub fn flood_fill<T, M, F>(map: &M, starts: &[Coord], conn: Connectivity, passable: F) -> HashSet<Coord>
+    where M: CoordMap<T>, F: Fn(&T) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = starts.iter()
+            .copied()
+            .filter(|&p| map.contains(p) && passable(map.at(p)))
+            .collect::<Vec<_>>();
+        seen.extend(stack.iter().copied());
+
+        while let Some(pos) = stack.pop() {
+            for n in conn.neighbours(pos) {
+                if map.contains(n) && !seen.contains(&n) && passable(map.at(n)) {
+                    seen.insert(n);
+                    stack.push(n);
+                }
+            }
+        }
+        seen
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Component {
+        pub size: usize,
+        pub min: Coord, // bounding box, inclusive
+        pub max: Coord,
+    }
+
+    pub struct Components {
+        pub labels: Vec<Vec<Option<usize>>>, // index into `components`, None for cells that aren't passable
+        pub components: Vec<Component>,
+    }
+
+    // Split the passable cells into connected regions
+    pub fn components<T>(map: &Vec<Vec<T>>, conn: Connectivity, passable: impl Fn(&T) -> bool) -> Components {
+        let mut labels = vec![vec![None; map[0].len()]; map.len()];
+        let mut components = vec![];
+
+        for y in 0..map.len() {
+            for x in 0..map[0].len() {
+                if labels[y][x].is_some() || !passable(&map[y][x]) {
+                    continue;
+                }
+                let label = components.len();
+                let region = flood_fill(map, &[Coord::new_u(x, y)], conn, &passable);
+                let mut comp = Component { size: region.len(), min: Coord::new_u(x, y), max: Coord::new_u(x, y) };
+                for p in region {
+                    labels[p.y_u()][p.x_u()] = Some(label);
+                    comp.min = Coord::new(comp.min.x.min(p.x), comp.min.y.min(p.y));
+                    comp.max = Coord::new(comp.max.x.max(p.x), comp.max.y.max(p.y));
+                }
+                components.push(comp);
+            }
+        }
+        Components { labels, components }
+    }
+
+    // Replace every cell with a `factor` x `factor` block. Useful when regions can squeeze between cells,
+    // e.g. between two pipes. Cell p ends up at p * factor in the new map.
+    pub fn upscale<T, U>(map: &[Vec<T>], factor: usize, block: impl Fn(Coord, &T) -> Vec<Vec<U>>) -> Vec<Vec<U>> {
+        let mut res: Vec<Vec<U>> = (0..map.len() * factor).map(|_| vec![]).collect();
+        for (y, row) in map.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let b = block(Coord::new_u(x, y), cell);
+                assert!(b.len() == factor && b.iter().all(|r| r.len() == factor));
+                for (dy, brow) in b.into_iter().enumerate() {
+                    res[y * factor + dy].extend(brow);
+                }
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn test_components() {
+        let map: Vec<Vec<char>> = ["#..#", "#.##", "##.."].iter().map(|row| row.chars().collect()).collect();
+        let comps = components(&map, Connectivity::Four, |&c| c == '.');
+        assert_eq!(vec![3, 2], comps.components.iter().map(|c| c.size).collect::<Vec<_>>());
+        assert_eq!(Coord::new(1, 0), comps.components[0].min);
+        assert_eq!(Coord::new(2, 1), comps.components[0].max);
+        assert_eq!(Some(1), comps.labels[2][3]);
+        assert_eq!(1, components(&map, Connectivity::Eight, |&c| c == '.').components.len());
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let mut grid = SparseGrid::new('.');
+        grid.set(Coord::new(-1, 2), '#');
+        grid.set(Coord::new(1, 0), '#');
+        assert_eq!(Some((Coord::new(-1, 0), Coord::new(1, 2))), grid.bounds());
+        assert_eq!("..#\n...\n#..\n", grid.render(|&c| c));
+        assert!(grid.to_dense(8).is_none());
+        assert_eq!(vec!['#', '.', '.'], grid.to_dense(9).unwrap()[2]);
+        let mut far = SparseGrid::new('.');
+        far.set(Coord::new(0, 0), '#');
+        far.set(Coord::new(1 << 40, 1 << 40), '#');
+        assert!(far.to_dense(usize::MAX).is_none());
+        assert_eq!(vec![Coord::new(1, 0), Coord::new(-1, 2)], grid.iter().map(|(p, _)| p).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_tiled_grid() {
+        let map = vec![vec!['a', 'b', 'c'], vec!['d', 'e', 'f']];
+        let tiled = TiledGrid::new(&map);
+        assert_eq!(Coord::new(2, 1), tiled.wrap(Coord::new(-1, -1)));
+        assert_eq!(Coord::new(0, 0), tiled.wrap(Coord::new(-3, -2)));
+        assert_eq!(Coord::new(2, 1), tiled.wrap(Coord::new(-4, -3)));
+        assert_eq!(Coord::new(1, 0), tiled.wrap(Coord::new(3 * 7 + 1, 2 * 5)));
+        assert_eq!(Coord::new(1, 1), tiled.wrap(Coord::new(-3 * 7 + 1, -2 * 5 + 1)));
+        assert_eq!('f', *tiled.at(Coord::new(-1, -1)));
+        assert_eq!('a', *tiled.at(Coord::new(-300, 200)));
+        assert_eq!(4, tiled.neighbours(Coord::new(-300, 200)).count());
+    }
+}
+
+pub mod geometry {
+    // Exact line intersections. The puzzle coordinates are big enough that f64 can't be trusted.
+    use num::{rational::Ratio, Zero};
+
+    pub type Rational = Ratio<i128>;
+
+    // The points pos + t * dir
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Line<const N: usize> {
+        pub pos: [i64; N],
+        pub dir: [i64; N],
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Intersection<const N: usize> {
+        pub t: Rational, // parameter on the first line
+        pub u: Rational, // parameter on the second line
+        pub point: [Rational; N],
+    }
+
+    impl<const N: usize> Line<N> {
+        pub fn new(pos: [i64; N], dir: [i64; N]) -> Self {
+            Line { pos, dir }
+        }
+
+        pub fn at(&self, t: Rational) -> [Rational; N] {
+            std::array::from_fn(|i| Rational::from(self.pos[i] as i128) + t * Rational::from(self.dir[i] as i128))
+        }
+
+        // Only keep the coordinates i and j
+        pub fn project(&self, i: usize, j: usize) -> Line<2> {
+            Line::new([self.pos[i], self.pos[j]], [self.dir[i], self.dir[j]])
+        }
+    }
+
+    // Solve l1.pos + t * l1.dir == l2.pos + u * l2.dir, None if the lines are parallel
+    pub fn intersect2(l1: &Line<2>, l2: &Line<2>) -> Option<Intersection<2>> {
+        let [dx1, dy1] = l1.dir.map(i128::from);
+        let [dx2, dy2] = l2.dir.map(i128::from);
+        let wx = l2.pos[0] as i128 - l1.pos[0] as i128;
+        let wy = l2.pos[1] as i128 - l1.pos[1] as i128;
+
+        let den = dx1 * dy2 - dy1 * dx2;
+        if den.is_zero() {
+            return None;
+        }
+        let t = Rational::new(wx * dy2 - wy * dx2, den);
+        let u = Rational::new(wx * dy1 - wy * dx1, den);
+        Some(Intersection { point: l1.at(t), t, u })
+    }
+
+    // Like intersect2, but also None if the lines miss each other
+    pub fn intersect3(l1: &Line<3>, l2: &Line<3>) -> Option<Intersection<3>> {
+        let (t, u) = [(0, 1), (0, 2), (1, 2)].iter().find_map(|&(i, j)| {
+            intersect2(&l1.project(i, j), &l2.project(i, j)).map(|is| (is.t, is.u))
+        })?;
+        let point = l1.at(t);
+        (point == l2.at(u)).then_some(Intersection { t, u, point })
+    }
+
+    #[test]
+    fn test_intersect() {
+        let l1 = Line::new([19, 13], [-2, 1]);
+        let l2 = Line::new([18, 19], [-1, -1]);
+        let is = intersect2(&l1, &l2).unwrap();
+        assert_eq!([Rational::new(43, 3), Rational::new(46, 3)], is.point);
+        assert_eq!(is.point, l2.at(is.u));
+        assert!(intersect2(&l2, &Line::new([20, 25], [-2, -2])).is_none());
+        // the positions are further apart than fits in an i64
+        let far = intersect2(&Line::new([-(1 << 62), 0], [1, 0]), &Line::new([1 << 62, 5], [0, 1])).unwrap();
+        assert_eq!([Rational::from(1i128 << 62), Rational::from(0)], far.point);
+
+        let l3 = Line::new([0, 0, 0], [1, 1, 1]);
+        let l4 = Line::new([2, 0, 1], [0, 1, 0]);
+        assert_eq!([2, 2, 2].map(Rational::from), intersect3(&l3, &Line::new([2, 0, 2], [0, 1, 0])).unwrap().point);
+        assert!(intersect3(&l3, &l4).is_none());
+    }
+}
+
+pub mod linalg {
+    // Exact Gaussian elimination for small dense systems, over the rationals or the integers modulo a prime
+    use std::{fmt, ops::{Add, Div, Mul, Neg, Sub}};
+
+    use num::{rational::Ratio, Integer, One, Zero};
+
+    pub trait Field: Clone + PartialEq + Zero + One + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {}
+
+    impl<T: Clone + Integer> Field for Ratio<T> {}
+
+    impl<const P: u64> Field for ModP<P> {}
+
+    // An integer modulo the prime P
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ModP<const P: u64>(u64);
+
+    impl<const P: u64> ModP<P> {
+        pub fn new(n: i64) -> Self {
+            ModP(n.rem_euclid(P as i64) as u64)
+        }
+
+        pub fn value(self) -> u64 {
+            self.0
+        }
+
+        pub fn pow(self, mut exp: u64) -> Self {
+            let mut base = self;
+            let mut res = Self::one();
+            while exp > 0 {
+                if exp % 2 == 1 {
+                    res = res * base;
+                }
+                base = base * base;
+                exp /= 2;
+            }
+            res
+        }
+
+        // Fermat's little theorem, panics for zero
+        pub fn inv(self) -> Self {
+            assert!(!self.is_zero(), "Zero has no inverse");
+            self.pow(P - 2)
+        }
+    }
+
+    impl<const P: u64> fmt::Display for ModP<P> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl<const P: u64> Add for ModP<P> {
+        type Output = Self;
+
+        fn add(self, rhs: Self) -> Self::Output {
+            ModP(((self.0 as u128 + rhs.0 as u128) % P as u128) as u64)
+        }
+    }
+
+    impl<const P: u64> Sub for ModP<P> {
+        type Output = Self;
+
+        fn sub(self, rhs: Self) -> Self::Output {
+            self + (-rhs)
+        }
+    }
+
+    impl<const P: u64> Neg for ModP<P> {
+        type Output = Self;
+
+        fn neg(self) -> Self::Output {
+            ModP((P - self.0) % P)
+        }
+    }
+
+    impl<const P: u64> Mul for ModP<P> {
+        type Output = Self;
+
+        fn mul(self, rhs: Self) -> Self::Output {
+            ModP(((self.0 as u128 * rhs.0 as u128) % P as u128) as u64)
+        }
+    }
+
+    impl<const P: u64> Div for ModP<P> {
+        type Output = Self;
+
+        #[allow(clippy::suspicious_arithmetic_impl)]
+        fn div(self, rhs: Self) -> Self::Output {
+            self * rhs.inv()
+        }
+    }
+
+    impl<const P: u64> Zero for ModP<P> {
+        fn zero() -> Self {
+            ModP(0)
+        }
+
+        fn is_zero(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    impl<const P: u64> One for ModP<P> {
+        fn one() -> Self {
+            ModP(1 % P)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Solution<F> {
+        pub rank: usize,
+        pub values: Vec<F>,  // a solution where the free variables are zero
+        pub free: Vec<usize>, // variables that can be chosen freely, empty if the solution is unique
+    }
+
+    // Reduce the augmented matrix [a | b] to reduced row echelon form, returning the pivot columns
+    fn eliminate<F: Field>(m: &mut [Vec<F>], cols: usize) -> Vec<usize> {
+        let mut pivots = vec![];
+        for c in 0..cols {
+            let r = pivots.len();
+            let Some(p) = (r..m.len()).find(|&i| !m[i][c].is_zero()) else {
+                continue;
+            };
+            m.swap(r, p);
+
+            let inv = F::one() / m[r][c].clone();
+            m[r].iter_mut().for_each(|x| *x = x.clone() * inv.clone());
+            for i in 0..m.len() {
+                if i != r && !m[i][c].is_zero() {
+                    let factor = m[i][c].clone();
+                    for k in c..m[i].len() {
+                        m[i][k] = m[i][k].clone() - factor.clone() * m[r][k].clone();
+                    }
+                }
+            }
+            pivots.push(c);
+        }
+        pivots
+    }
+
+    // Solve a * x = b, None if there is no solution
+    pub fn solve<F: Field>(a: &[Vec<F>], b: &[F]) -> Option<Solution<F>> {
+        assert!(a.len() == b.len());
+        let cols = a.first().map_or(0, |row| row.len());
+        let mut m: Vec<Vec<F>> = a.iter().zip(b).map(|(row, x)| {
+            assert!(row.len() == cols);
+            row.iter().cloned().chain([x.clone()]).collect()
+        }).collect();
+
+        let pivots = eliminate(&mut m, cols);
+        let rank = pivots.len();
+        if m[rank..].iter().any(|row| !row[cols].is_zero()) {
+            return None;
+        }
+
+        let mut values = vec![F::zero(); cols];
+        for (row, &c) in pivots.iter().enumerate() {
+            values[c] = m[row][cols].clone();
+        }
+        let free = (0..cols).filter(|c| !pivots.contains(c)).collect();
+        Some(Solution { rank, values, free })
+    }
+
+    pub fn rank<F: Field>(a: &[Vec<F>]) -> usize {
+        let cols = a.first().map_or(0, |row| row.len());
+        eliminate(&mut a.to_vec(), cols).len()
+    }
+
+    #[test]
+    fn test_solve() {
+        type Q = Ratio<i64>;
+        let q = |rows: &[&[i64]]| rows.iter().map(|row| row.iter().map(|&x| Q::from(x)).collect()).collect::<Vec<Vec<Q>>>();
+
+        let a = q(&[&[2, 1, -1], &[-3, -1, 2], &[-2, 1, 2]]);
+        let sol = solve(&a, &[8, -11, -3].map(Q::from)).unwrap();
+        assert_eq!(vec![2, 3, -1].into_iter().map(Q::from).collect::<Vec<_>>(), sol.values);
+        assert_eq!(3, sol.rank);
+
+        let a = q(&[&[1, 2], &[2, 4]]);
+        assert!(solve(&a, &[1, 3].map(Q::from)).is_none());
+        let sol = solve(&a, &[1, 2].map(Q::from)).unwrap();
+        assert_eq!((1, vec![1]), (sol.rank, sol.free));
+        assert_eq!(1, rank(&a));
+
+        type M7 = ModP<7>;
+        let a: Vec<Vec<M7>> = vec![vec![M7::new(3), M7::new(1)], vec![M7::new(1), M7::new(2)]];
+        let sol = solve(&a, &[M7::new(4), M7::new(-5)]).unwrap();
+        assert_eq!(vec![M7::new(4), M7::new(6)], sol.values);
+    }
+}
+
+pub mod parallel {
+    use std::{sync::atomic::{AtomicUsize, Ordering}, thread};
+
+    use itertools::Itertools;
+
+    pub fn threads() -> usize {
+        thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    }
+
+    // Map `f` over `items` using all available cores. The results are in the same order as the items.
+    pub fn par_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+    where T: Sync, R: Send, F: Fn(&T) -> R + Sync {
+        par_map_n(items, threads(), f)
+    }
+
+    // Like `par_map` but with a fixed number of workers. Workers pick the next unhandled item when they
+    // are done, so it's fine if some items take much longer than others.
+    pub fn par_map_n<T, R, F>(items: &[T], workers: usize, f: F) -> Vec<R>
+    where T: Sync, R: Send, F: Fn(&T) -> R + Sync {
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<Option<R>> = items.iter().map(|_| None).collect();
+
+        thread::scope(|s| {
+            let handles = (0..workers.clamp(1, items.len().max(1))).map(|_| {
+                s.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= items.len() {
+                            return done;
+                        }
+                        done.push((i, f(&items[i])));
+                    }
+                })
+            }).collect_vec();
+
+            for handle in handles {
+                for (i, res) in handle.join().unwrap() {
+                    results[i] = Some(res);
+                }
+            }
+        });
+        results.into_iter().map(|res| res.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_par_map() {
+        // the early items take the longest, so they finish last
+        let items = (0..20u64).collect_vec();
+        let slow = |&n: &u64| {
+            thread::sleep(std::time::Duration::from_millis(20 - n));
+            n * n
+        };
+        let squares = items.iter().map(|n| n * n).collect_vec();
+        assert_eq!(squares, par_map_n(&items, 4, slow));
+        assert_eq!(squares, par_map(&items, slow));
+
+        assert_eq!(vec![2, 4, 6], par_map_n(&[1, 2, 3], 16, |n| n * 2));
+        assert_eq!(vec![2, 4, 6], par_map_n(&[1, 2, 3], 0, |n| n * 2));
+        assert_eq!(Vec::<i32>::new(), par_map_n(&[], 4, |n: &i32| n * 2));
+        assert_eq!(Vec::<i32>::new(), par_map_n(&[], 0, |n: &i32| n * 2));
+    }
+}
+
+
+pub mod stats {
+    // Timing and allocation statistics for each part of a day. Nothing is reported unless the
+    // AOC_STATS environment variable is set, which the runner does. The allocation counts need the
+    // `alloc-stats` feature since it replaces the global allocator.
+    use std::{env, time::Instant};
+
+    pub const ENV: &str = "AOC_STATS";
+    pub const PREFIX: &str = "stats";
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct Allocs {
+        pub peak: usize,  // bytes above what was allocated when the part started
+        pub total: usize, // bytes
+        pub count: usize,
+    }
+
+    #[cfg(feature = "alloc-stats")]
+    mod counting {
+        use std::{alloc::{GlobalAlloc, Layout, System}, sync::atomic::{AtomicUsize, Ordering}};
+
+        use super::Allocs;
+
+        static CURRENT: AtomicUsize = AtomicUsize::new(0);
+        static PEAK: AtomicUsize = AtomicUsize::new(0);
+        static TOTAL: AtomicUsize = AtomicUsize::new(0);
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counting;
+
+        #[global_allocator]
+        static ALLOCATOR: Counting = Counting;
+
+        fn grow(size: usize) {
+            COUNT.fetch_add(1, Ordering::Relaxed);
+            TOTAL.fetch_add(size, Ordering::Relaxed);
+            let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+
+        unsafe impl GlobalAlloc for Counting {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let ptr = System.alloc(layout);
+                if !ptr.is_null() {
+                    grow(layout.size());
+                }
+                ptr
+            }
+
+            unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+                let ptr = System.alloc_zeroed(layout);
+                if !ptr.is_null() {
+                    grow(layout.size());
+                }
+                ptr
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout);
+                CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            }
+
+            // A realloc counts as a new allocation of the new size
+            unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+                let new_ptr = System.realloc(ptr, layout, new_size);
+                if !new_ptr.is_null() {
+                    CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+                    grow(new_size);
+                }
+                new_ptr
+            }
+        }
+
+        pub struct Snapshot {
+            current: usize,
+            total: usize,
+            count: usize,
+        }
+
+        pub fn start() -> Option<Snapshot> {
+            let current = CURRENT.load(Ordering::Relaxed);
+            PEAK.store(current, Ordering::Relaxed);
+            Some(Snapshot { current, total: TOTAL.load(Ordering::Relaxed), count: COUNT.load(Ordering::Relaxed) })
+        }
+
+        pub fn since(snap: Snapshot) -> Allocs {
+            Allocs {
+                peak: PEAK.load(Ordering::Relaxed).saturating_sub(snap.current),
+                total: TOTAL.load(Ordering::Relaxed) - snap.total,
+                count: COUNT.load(Ordering::Relaxed) - snap.count,
+            }
+        }
+    }
+
+    #[cfg(not(feature = "alloc-stats"))]
+    mod counting {
+        use super::Allocs;
+
+        pub enum Snapshot {}
+
+        pub fn start() -> Option<Snapshot> {
+            None
+        }
+
+        pub fn since(snap: Snapshot) -> Allocs {
+            match snap {}
+        }
+    }
+
+    // Run one part of a day, reporting the time it took and what it allocated on stderr.
+    // `name` shouldn't contain whitespace.
+    pub fn part<R>(name: &str, f: impl FnOnce() -> R) -> R {
+        if env::var_os(ENV).is_none() {
+            return f();
+        }
+
+        let snap = counting::start();
+        let start = Instant::now();
+        let res = f();
+        let time = start.elapsed();
+
+        let mut line = format!("{PREFIX} {name} time_us={}", time.as_micros());
+        if let Some(allocs) = snap.map(counting::since) {
+            line += &format!(" peak={} total={} count={}", allocs.peak, allocs.total, allocs.count);
+        }
+        eprintln!("{line}");
+        res
+    }
+}
+
+// Generates a `#[test]` for each known answer of a day, so that `cargo test` checks all of them.
+// Answers for an input file are written `name("file"): |file| solve(file) => answer;` and are
+// skipped when the file is missing, since the real inputs can't be shared. Answers that don't need
+// a file are written `name: solve() => answer;`.
+//
+//     aoc2023::answers! {
+//         ex1("inputs/day1_ex1.txt"): |f| solve1(&parse(f)) => 142;
+//         sol1("inputs/day1.txt"): |f| solve1(&parse(f)) => 54644;
+//     }
+#[macro_export]
+macro_rules! answers {
+    ($($tests:tt)*) => {
+        #[cfg(test)]
+        mod answers {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::answer_tests!($($tests)*);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! answer_tests {
+    () => {};
+    ($name:ident($file:literal): $solve:expr => $answer:expr; $($rest:tt)*) => {
+        #[test]
+        fn $name() {
+            if !std::path::Path::new($file).exists() {
+                eprintln!("skipping {}, {} is missing", stringify!($name), $file);
+                return;
+            }
+            assert_eq!($answer, ($solve)($file));
+        }
+        $crate::answer_tests!($($rest)*);
+    };
+    ($name:ident: $solve:expr => $answer:expr; $($rest:tt)*) => {
+        #[test]
+        fn $name() {
+            assert_eq!($answer, $solve);
+        }
+        $crate::answer_tests!($($rest)*);
+    };
+}